@@ -0,0 +1,156 @@
+//! Benchmarks of the headless renderer. Run with
+//! `cargo bench -p easygpu --features blocking`;
+//! see the README for comparing against a baseline.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use easygpu::figures::Size;
+use easygpu::prelude::*;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Returns `None` when there is no adapter, so benchmarks are skipped instead
+/// of failing on machines without a GPU.
+fn renderer() -> Option<Renderer<'static>> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+    RendererBuilder::new()
+        .with_offscreen(true, adapter, 1)
+        .build_blocking()
+        .ok()
+}
+
+fn buffer_creation(c: &mut Criterion) {
+    let Some(renderer) = renderer() else {
+        eprintln!("no adapter found, skipping buffer benchmarks");
+        return;
+    };
+    let mut group = c.benchmark_group("buffer_creation");
+    for kib in [1, 64, 1024] {
+        let data = vec![0_u8; kib * 1024];
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_with_input(BenchmarkId::new("vertex", kib), &data, |b, data| {
+            b.iter(|| renderer.vertex_buffer(data));
+        });
+    }
+    group.finish();
+}
+
+fn texture_upload(c: &mut Criterion) {
+    let Some(mut renderer) = renderer() else {
+        eprintln!("no adapter found, skipping texture benchmarks");
+        return;
+    };
+    let mut group = c.benchmark_group("texture_upload");
+    for side in [256, 1024] {
+        let size = Size::new(side, side);
+        let texture = renderer.texture(
+            size,
+            FORMAT,
+            wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING,
+            false,
+        );
+        let texels = vec![Rgba8::default(); size.area() as usize];
+        group.throughput(Throughput::Bytes(
+            (texels.len() * std::mem::size_of::<Rgba8>()) as u64,
+        ));
+        group.bench_with_input(BenchmarkId::new("fill", side), &texels, |b, texels| {
+            b.iter(|| {
+                renderer.submit(&[Op::Fill(&texture, texels)]);
+                renderer.wait_idle();
+            });
+        });
+    }
+    group.finish();
+}
+
+const SPRITE_SHADER: &str = "
+@group(0) @binding(0) var<uniform> ortho: mat4x4<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) corner: vec2<f32>, @location(1) offset: vec2<f32>) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = ortho * vec4<f32>(corner * 8.0 + offset, 0.0, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+}
+";
+
+/// Records and submits one draw call per sprite.
+fn sprite_draws(c: &mut Criterion) {
+    let Some(mut renderer) = renderer() else {
+        eprintln!("no adapter found, skipping sprite benchmarks");
+        return;
+    };
+    let size = Size::new(512, 512);
+    let pipeline: DynamicPipeline<[f32; 16]> = renderer
+        .dynamic_pipeline_blocking(
+            DynamicPipelineDescription {
+                vertex_layouts: VertexLayout::from_buffers(&[
+                    VertexBufferDesc {
+                        formats: &[VertexFormat::Floatx2],
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                    },
+                    VertexBufferDesc {
+                        formats: &[VertexFormat::Floatx2],
+                        step_mode: wgpu::VertexStepMode::Instance,
+                    },
+                ]),
+                pipeline_layout: vec![vec![Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStages::VERTEX,
+                }]],
+                shader: SPRITE_SHADER.to_owned(),
+            },
+            Blending::default(),
+            FORMAT,
+        )
+        .expect("invalid sprite pipeline");
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(size, 1).to_array(),
+    );
+    let quad = renderer.mesh(
+        &[[0_f32, 0.], [1., 0.], [1., 1.], [0., 1.]],
+        &[0_u16, 1, 2, 0, 2, 3],
+    );
+    let framebuffer = renderer.framebuffer(size, FORMAT);
+
+    let mut group = c.benchmark_group("sprite_draws");
+    for count in [100_u32, 1_000, 10_000] {
+        let offsets = (0..count)
+            .map(|index| [(index % 64 * 8) as f32, (index / 64 % 64 * 8) as f32])
+            .collect::<Vec<_>>();
+        let instances = renderer.vertex_buffer(&offsets);
+        group.throughput(Throughput::Elements(u64::from(count)));
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| {
+                let mut frame = renderer.frame();
+                {
+                    let mut pass = frame.pass(PassOp::Clear(Rgba::TRANSPARENT), &framebuffer, None);
+                    pass.set_easy_pipeline(&pipeline);
+                    pass.set_easy_vertex_buffer(&quad.vertices);
+                    pass.set_easy_vertex_buffer_at(1, &instances);
+                    pass.set_easy_index_buffer(&quad.indices);
+                    for sprite in 0..count {
+                        RenderPassExt::draw_indexed(&mut pass, 0..6, sprite..sprite + 1);
+                    }
+                }
+                renderer.present(frame);
+                renderer.wait_idle();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, buffer_creation, texture_upload, sprite_draws);
+criterion_main!(benches);
@@ -0,0 +1,338 @@
+//! Frame-by-frame animation over regions of a texture atlas.
+
+use std::time::Duration;
+
+use figures::{Point, Rect, Size};
+
+/// What an `Animation` does after its last frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Playback {
+    /// Starts over from the first frame.
+    #[default]
+    Loop,
+    /// Plays the frames backwards to the first one, then forwards again.
+    PingPong,
+    /// Stops on the last frame.
+    Once,
+}
+
+/// A region of an atlas, in texels, shown for `duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationFrame {
+    pub region: Rect<u32>,
+    pub duration: Duration,
+}
+
+impl AnimationFrame {
+    pub const fn new(region: Rect<u32>, duration: Duration) -> Self {
+        Self { region, duration }
+    }
+}
+
+/// A sequence of atlas regions played back over time.
+///
+/// Call `advance` with the time since the last frame, then draw the sprite
+/// with the texture coordinates returned by `uv`.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    playback: Playback,
+    current: usize,
+    /// Time spent on the current frame.
+    elapsed: Duration,
+    reversed: bool,
+    finished: bool,
+}
+
+impl Animation {
+    /// Panics if there are no frames, or if any frame has a zero duration.
+    pub fn new(frames: Vec<AnimationFrame>, playback: Playback) -> Self {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        assert!(
+            frames.iter().all(|frame| !frame.duration.is_zero()),
+            "animation frames must have a non-zero duration"
+        );
+        Self {
+            frames,
+            playback,
+            current: 0,
+            elapsed: Duration::ZERO,
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    /// An animation showing each region for the same `duration`.
+    pub fn uniform<I: IntoIterator<Item = Rect<u32>>>(
+        regions: I,
+        duration: Duration,
+        playback: Playback,
+    ) -> Self {
+        Self::new(
+            regions
+                .into_iter()
+                .map(|region| AnimationFrame::new(region, duration))
+                .collect(),
+            playback,
+        )
+    }
+
+    /// An animation over `count` cells of `cell` size, laid out left to right
+    /// and top to bottom in rows of `columns`, starting at `origin`.
+    pub fn grid(
+        origin: Point<u32>,
+        cell: Size<u32>,
+        columns: u32,
+        count: u32,
+        duration: Duration,
+        playback: Playback,
+    ) -> Self {
+        assert!(columns > 0, "a grid needs at least one column");
+        Self::uniform(
+            (0..count).map(|index| {
+                Rect::new(
+                    Point::new(
+                        origin.x + index % columns * cell.width,
+                        origin.y + index / columns * cell.height,
+                    ),
+                    cell,
+                )
+            }),
+            duration,
+            playback,
+        )
+    }
+
+    /// Moves the animation forward by `dt`.
+    pub fn advance(&mut self, dt: Duration) {
+        if self.finished {
+            return;
+        }
+        self.elapsed += dt;
+
+        // Skip whole cycles at once, so long pauses don't step every frame.
+        if self.playback != Playback::Once {
+            let cycle = self.cycle().as_nanos();
+            let skipped = self.elapsed.as_nanos() / cycle * cycle;
+            self.elapsed -= Duration::from_nanos(skipped as u64);
+        }
+
+        while self.elapsed >= self.frames[self.current].duration {
+            self.elapsed -= self.frames[self.current].duration;
+            self.step();
+            if self.finished {
+                self.elapsed = Duration::ZERO;
+                break;
+            }
+        }
+    }
+
+    /// Returns to the first frame.
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.elapsed = Duration::ZERO;
+        self.reversed = false;
+        self.finished = false;
+    }
+
+    /// Returns true once a `Playback::Once` animation has shown its last
+    /// frame for that frame's full duration. The animation keeps showing the
+    /// last frame afterwards.
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub const fn playback(&self) -> Playback {
+        self.playback
+    }
+
+    pub fn set_playback(&mut self, playback: Playback) {
+        self.playback = playback;
+        self.finished = false;
+    }
+
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    /// The index of the frame currently shown.
+    pub const fn frame_index(&self) -> usize {
+        self.current
+    }
+
+    pub fn current_frame(&self) -> &AnimationFrame {
+        &self.frames[self.current]
+    }
+
+    /// The atlas region of the current frame, in texels.
+    pub fn region(&self) -> Rect<u32> {
+        self.current_frame().region
+    }
+
+    /// The texture coordinates of the current frame in an atlas of
+    /// `atlas_size` texels.
+    pub fn uv(&self, atlas_size: Size<u32>) -> Rect<f32> {
+        let region = self.region();
+        let (width, height) = (atlas_size.width as f32, atlas_size.height as f32);
+        Rect::new(
+            Point::new(
+                region.origin.x as f32 / width,
+                region.origin.y as f32 / height,
+            ),
+            Size::new(
+                region.size.width as f32 / width,
+                region.size.height as f32 / height,
+            ),
+        )
+    }
+
+    /// The time it takes to return to the current frame, playing in the
+    /// current direction.
+    fn cycle(&self) -> Duration {
+        let total = self.frames.iter().map(|frame| frame.duration).sum();
+        match self.playback {
+            Playback::PingPong if self.frames.len() > 2 => {
+                let inner = &self.frames[1..self.frames.len() - 1];
+                total + inner.iter().map(|frame| frame.duration).sum::<Duration>()
+            }
+            _ => total,
+        }
+    }
+
+    fn step(&mut self) {
+        let last = self.frames.len() - 1;
+        match self.playback {
+            Playback::Loop => {
+                self.current = if self.current == last {
+                    0
+                } else {
+                    self.current + 1
+                }
+            }
+            Playback::Once => {
+                if self.current == last {
+                    self.finished = true;
+                } else {
+                    self.current += 1;
+                }
+            }
+            Playback::PingPong if last == 0 => {}
+            Playback::PingPong => {
+                if self.current == last {
+                    self.reversed = true;
+                } else if self.current == 0 {
+                    self.reversed = false;
+                }
+                if self.reversed {
+                    self.current -= 1;
+                } else {
+                    self.current += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(10);
+
+    fn animation(frames: u32, playback: Playback) -> Animation {
+        Animation::grid(
+            Point::new(0, 0),
+            Size::new(8, 8),
+            frames,
+            frames,
+            FRAME,
+            playback,
+        )
+    }
+
+    /// The frame indices shown after each step of `FRAME`.
+    fn steps(animation: &mut Animation, count: usize) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                animation.advance(FRAME);
+                animation.frame_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_wraps_to_the_first_frame() {
+        let mut animation = animation(3, Playback::Loop);
+        assert_eq!(steps(&mut animation, 4), [1, 2, 0, 1]);
+    }
+
+    #[test]
+    fn loop_wraps_when_dt_exceeds_the_period() {
+        let mut animation = animation(4, Playback::Loop);
+        animation.advance(FRAME * 4 * 3 + FRAME + FRAME / 2);
+        assert_eq!(animation.frame_index(), 1);
+        // The remainder carries over into the next advance.
+        animation.advance(FRAME / 2);
+        assert_eq!(animation.frame_index(), 2);
+    }
+
+    #[test]
+    fn ping_pong_reverses_on_the_last_and_first_frames() {
+        let mut animation = animation(4, Playback::PingPong);
+        assert_eq!(steps(&mut animation, 8), [1, 2, 3, 2, 1, 0, 1, 2]);
+    }
+
+    #[test]
+    fn ping_pong_keeps_its_direction_when_dt_exceeds_the_period() {
+        let mut animation = animation(4, Playback::PingPong);
+        // A full cycle is 0, 1, 2, 3, 2, 1.
+        animation.advance(FRAME * 6 * 2 + FRAME * 4);
+        assert_eq!(animation.frame_index(), 2);
+        assert_eq!(steps(&mut animation, 3), [1, 0, 1]);
+    }
+
+    #[test]
+    fn once_clamps_to_the_last_frame() {
+        let mut animation = animation(3, Playback::Once);
+        animation.advance(FRAME * 2 + FRAME / 2);
+        assert_eq!(animation.frame_index(), 2);
+        assert!(!animation.is_finished());
+
+        animation.advance(Duration::from_secs(1));
+        assert_eq!(animation.frame_index(), 2);
+        assert!(animation.is_finished());
+
+        animation.advance(FRAME);
+        assert_eq!(animation.frame_index(), 2);
+
+        animation.reset();
+        assert_eq!(animation.frame_index(), 0);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn single_frames_never_move() {
+        for playback in [Playback::Loop, Playback::PingPong, Playback::Once] {
+            let mut animation = animation(1, playback);
+            assert_eq!(steps(&mut animation, 3), [0, 0, 0]);
+        }
+    }
+
+    #[test]
+    fn grid_regions_fill_rows_first() {
+        let animation = Animation::grid(
+            Point::new(4, 2),
+            Size::new(8, 16),
+            2,
+            3,
+            FRAME,
+            Playback::Loop,
+        );
+        let origins: Vec<_> = animation
+            .frames()
+            .iter()
+            .map(|frame| (frame.region.origin.x, frame.region.origin.y))
+            .collect();
+        assert_eq!(origins, [(4, 2), (12, 2), (4, 18)]);
+    }
+}
@@ -1,5 +1,9 @@
 use wgpu::ShaderStages;
 
+use crate::buffers::UniformBuffer;
+use crate::context::GpuContext;
+use crate::sampler::Sampler;
+
 /// A group of bindings.
 #[derive(Debug)]
 pub struct BindingGroup {
@@ -21,21 +25,167 @@ pub struct BindingGroupLayout {
     pub wgpu: wgpu::BindGroupLayout,
     pub size: usize,
     pub set_index: u32,
+    /// The type of each slot, in binding order.
+    pub slots: Vec<BindingType>,
 }
 
 impl BindingGroupLayout {
-    pub fn new(set_index: u32, layout: wgpu::BindGroupLayout, size: usize) -> Self {
+    pub fn new(set_index: u32, layout: wgpu::BindGroupLayout, slots: Vec<BindingType>) -> Self {
         Self {
             wgpu: layout,
-            size,
+            size: slots.len(),
             set_index,
+            slots,
+        }
+    }
+}
+
+/// Builds a `BindingGroup`, checking that each resource matches the type of
+/// the slot it is bound to.
+///
+/// Resources are bound to slots in order. Building with the wrong kind of
+/// resource in a slot, or with unfilled slots, panics with a description of
+/// the slot.
+#[derive(Debug)]
+pub struct BindingGroupBuilder<'a> {
+    layout: &'a BindingGroupLayout,
+    entries: Vec<wgpu::BindGroupEntry<'a>>,
+    resources: Vec<Resource>,
+}
+
+impl<'a> BindingGroupBuilder<'a> {
+    pub fn new(layout: &'a BindingGroupLayout) -> Self {
+        Self {
+            layout,
+            entries: Vec::with_capacity(layout.size),
+            resources: Vec::with_capacity(layout.size),
+        }
+    }
+
+    /// Binds a uniform buffer to the next slot.
+    pub fn uniforms(self, buffer: &'a UniformBuffer) -> Self {
+        self.push(Resource::UniformBuffer, buffer)
+    }
+
+    /// Binds a sampler to the next slot.
+    pub fn sampler(self, sampler: &'a Sampler) -> Self {
+        self.push(Resource::Sampler, sampler)
+    }
+
+    /// Binds a texture view, such as a `Texture` or `Framebuffer`, to the next
+    /// slot.
+    pub fn texture<T: AsRef<wgpu::TextureView>>(self, texture: &'a T) -> Self {
+        self.push(Resource::Texture, texture.as_ref())
+    }
+
+    /// Binds a texture view to the next slot for direct texel access from
+    /// shaders. The texture needs `TextureUsages::STORAGE_BINDING`.
+    pub fn storage_texture<T: AsRef<wgpu::TextureView>>(self, texture: &'a T) -> Self {
+        self.push(Resource::StorageTexture, texture.as_ref())
+    }
+
+    pub fn build(self, device: &GpuContext) -> BindingGroup {
+        if let Err(err) = check_slots(&self.layout.slots, &self.resources) {
+            panic!("binding group {}: {err}", self.layout.set_index);
+        }
+        BindingGroup::new(
+            self.layout.set_index,
+            device.wgpu.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.layout.wgpu,
+                label: None,
+                entries: self.entries.as_slice(),
+            }),
+        )
+    }
+
+    fn push(mut self, resource: Resource, bind: &'a dyn Bind) -> Self {
+        self.entries.push(bind.binding(self.entries.len() as u32));
+        self.resources.push(resource);
+        self
+    }
+}
+
+/// The kind of resource bound to a slot by `BindingGroupBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resource {
+    UniformBuffer,
+    Sampler,
+    Texture,
+    StorageTexture,
+}
+
+impl Resource {
+    fn fits(self, slot: BindingType) -> bool {
+        match self {
+            Self::UniformBuffer => matches!(
+                slot,
+                BindingType::UniformBuffer | BindingType::UniformBufferDynamic
+            ),
+            Self::Sampler => matches!(slot, BindingType::Sampler),
+            Self::Texture => matches!(slot, BindingType::SampledTexture { .. }),
+            Self::StorageTexture => matches!(slot, BindingType::StorageTexture { .. }),
         }
     }
 }
 
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::UniformBuffer => "uniform buffer",
+            Self::Sampler => "sampler",
+            Self::Texture => "texture",
+            Self::StorageTexture => "storage texture",
+        })
+    }
+}
+
+/// Why the resources of a `BindingGroupBuilder` don't fit its layout.
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+enum SlotError {
+    #[error("slot {index} expects {expected:?}, but a {bound} was bound")]
+    Mismatch {
+        index: usize,
+        expected: BindingType,
+        bound: Resource,
+    },
+    #[error("has {slots} slots but {bound} were bound")]
+    Count { slots: usize, bound: usize },
+}
+
+/// Checks that `resources` fill every slot with a resource of its type.
+fn check_slots(slots: &[BindingType], resources: &[Resource]) -> Result<(), SlotError> {
+    if slots.len() != resources.len() {
+        return Err(SlotError::Count {
+            slots: slots.len(),
+            bound: resources.len(),
+        });
+    }
+    match slots
+        .iter()
+        .zip(resources)
+        .position(|(slot, resource)| !resource.fits(*slot))
+    {
+        Some(index) => Err(SlotError::Mismatch {
+            index,
+            expected: slots[index],
+            bound: resources[index],
+        }),
+        None => Ok(()),
+    }
+}
+
 /// A trait representing a resource that can be bound.
 pub trait Bind {
-    fn binding(&self, index: u32) -> wgpu::BindGroupEntry;
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_>;
+}
+
+impl Bind for wgpu::TextureView {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding: index,
+            resource: wgpu::BindingResource::TextureView(self),
+        }
+    }
 }
 
 /// A binding type.
@@ -44,7 +194,16 @@ pub enum BindingType {
     UniformBuffer,
     UniformBufferDynamic,
     Sampler,
-    SampledTexture { multisampled: bool },
+    SampledTexture {
+        multisampled: bool,
+    },
+    /// A 2D texture whose texels are read or written directly, such as by a
+    /// compute shader, declared as `texture_storage_2d` in WGSL. See
+    /// `GpuContext::create_storage_texture`.
+    StorageTexture {
+        access: wgpu::StorageTextureAccess,
+        format: wgpu::TextureFormat,
+    },
 }
 
 impl From<BindingType> for wgpu::BindingType {
@@ -66,6 +225,11 @@ impl From<BindingType> for wgpu::BindingType {
                 view_dimension: wgpu::TextureViewDimension::D2,
             },
             BindingType::Sampler => wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            BindingType::StorageTexture { access, format } => wgpu::BindingType::StorageTexture {
+                access,
+                format,
+                view_dimension: wgpu::TextureViewDimension::D2,
+            },
         }
     }
 }
@@ -75,3 +239,75 @@ pub struct Binding {
     pub binding: BindingType,
     pub stage: ShaderStages,
 }
+
+impl AsRef<wgpu::BindGroup> for BindingGroup {
+    fn as_ref(&self) -> &wgpu::BindGroup {
+        &self.wgpu
+    }
+}
+
+impl AsRef<wgpu::BindGroupLayout> for BindingGroupLayout {
+    fn as_ref(&self) -> &wgpu::BindGroupLayout {
+        &self.wgpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLOTS: [BindingType; 3] = [
+        BindingType::UniformBufferDynamic,
+        BindingType::SampledTexture {
+            multisampled: false,
+        },
+        BindingType::Sampler,
+    ];
+
+    #[test]
+    fn matching_resources_fit() {
+        let resources = [
+            Resource::UniformBuffer,
+            Resource::Texture,
+            Resource::Sampler,
+        ];
+        assert!(check_slots(&SLOTS, &resources).is_ok());
+        assert!(check_slots(&[], &[]).is_ok());
+    }
+
+    #[test]
+    fn mismatched_slots_are_errors() {
+        let resources = [
+            Resource::UniformBuffer,
+            Resource::Sampler,
+            Resource::Texture,
+        ];
+        let err = check_slots(&SLOTS, &resources).unwrap_err();
+        assert!(matches!(
+            err,
+            SlotError::Mismatch {
+                index: 1,
+                expected: BindingType::SampledTexture { .. },
+                bound: Resource::Sampler,
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "slot 1 expects SampledTexture { multisampled: false }, but a sampler was bound"
+        );
+    }
+
+    #[test]
+    fn unfilled_slots_are_errors() {
+        let resources = [Resource::UniformBuffer, Resource::Texture];
+        assert!(matches!(
+            check_slots(&SLOTS, &resources),
+            Err(SlotError::Count { slots: 3, bound: 2 })
+        ));
+        let extra = [Resource::StorageTexture];
+        assert!(matches!(
+            check_slots(&[], &extra),
+            Err(SlotError::Count { slots: 0, bound: 1 })
+        ));
+    }
+}
@@ -7,5 +7,75 @@ pub struct DepthBuffer {
 }
 
 impl DepthBuffer {
+    /// The default depth format. See `DeviceBuilder::with_depth_format`.
     pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    /// Clears the depth to `depth` and any stencil to 0.
+    ///
+    /// Depth textures can't be written by buffer copies, so this records an
+    /// empty render pass instead.
+    pub fn clear(&self, depth: f32, encoder: &mut wgpu::CommandEncoder) {
+        let format = self.texture.format;
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.texture.view,
+                depth_ops: format.has_depth_aspect().then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(depth),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: format.has_stencil_aspect().then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Store,
+                }),
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+    }
+}
+
+/// How depth values are compared and cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    /// Depth increases away from the camera. Depth is cleared to 1.0 and
+    /// fragments pass when their depth is less than or equal to the stored
+    /// depth.
+    #[default]
+    Standard,
+    /// Depth decreases away from the camera, which distributes floating point
+    /// precision more evenly over large depth ranges. Depth is cleared to 0.0
+    /// and fragments pass when their depth is greater than or equal to the
+    /// stored depth. Use with `ScreenTransformation::perspective_reverse_z`.
+    ReverseZ,
+}
+
+impl DepthMode {
+    pub const fn compare(self) -> wgpu::CompareFunction {
+        match self {
+            DepthMode::Standard => wgpu::CompareFunction::LessEqual,
+            DepthMode::ReverseZ => wgpu::CompareFunction::GreaterEqual,
+        }
+    }
+
+    /// The depth of the far plane, which depth buffers are cleared to.
+    pub const fn clear_value(self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.,
+            DepthMode::ReverseZ => 0.,
+        }
+    }
+}
+
+impl AsRef<wgpu::Texture> for DepthBuffer {
+    fn as_ref(&self) -> &wgpu::Texture {
+        &self.texture.wgpu
+    }
+}
+
+impl AsRef<wgpu::TextureView> for DepthBuffer {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.texture.view
+    }
 }
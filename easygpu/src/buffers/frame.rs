@@ -1,12 +1,11 @@
-use figures::Rect;
+use figures::{Rect, Size};
 
 use crate::binding::Bind;
 use crate::buffers::DepthBuffer;
-use crate::canvas::Canvas;
-use crate::color::Bgra8;
+use crate::canvas::{Canvas, Texel};
 use crate::device::Device;
 use crate::renderer::RenderTarget;
-use crate::texture::Texture;
+use crate::texture::{Origin, SubResource, Texture};
 /// Off-screen framebuffer. Can be used as a render target in render passes.
 #[derive(Debug)]
 pub struct Framebuffer {
@@ -39,10 +38,33 @@ impl RenderTarget for Framebuffer {
     fn zdepth_target(&self) -> &wgpu::TextureView {
         &self.depth.texture.view
     }
+
+    fn zdepth_format(&self) -> wgpu::TextureFormat {
+        self.depth.texture.format
+    }
+
+    fn color_format(&self) -> Option<wgpu::TextureFormat> {
+        Some(self.texture.format)
+    }
+    fn target_size(&self) -> Option<Size<u32>> {
+        Some(self.texture.size)
+    }
+}
+
+impl AsRef<wgpu::TextureView> for Framebuffer {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.texture.view
+    }
+}
+
+impl AsRef<wgpu::Texture> for Framebuffer {
+    fn as_ref(&self) -> &wgpu::Texture {
+        &self.texture.wgpu
+    }
 }
 
 impl Bind for Framebuffer {
-    fn binding(&self, index: u32) -> wgpu::BindGroupEntry {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding: index,
             resource: wgpu::BindingResource::TextureView(&self.texture.view),
@@ -50,21 +72,21 @@ impl Bind for Framebuffer {
     }
 }
 
-impl Canvas for Framebuffer {
-    type Color = Bgra8;
-
-    fn clear(&self, color: Self::Color, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+impl<T: Texel> Canvas<T> for Framebuffer {
+    /// Clears the color texture to `color`, and the depth buffer to the clear
+    /// value of the device's `DepthMode`.
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::clear(&self.texture, color, device, encoder);
-        Texture::clear(&self.depth.texture, 0f32, device, encoder);
+        self.depth.clear(device.depth_mode().clear_value(), encoder);
     }
 
-    fn fill(&self, buf: &[Self::Color], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::fill(&self.texture, buf, device, encoder);
     }
 
     fn transfer(
         &self,
-        buf: &[Self::Color],
+        buf: &[T],
         rect: Rect<i32>,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
@@ -72,12 +94,42 @@ impl Canvas for Framebuffer {
         Texture::transfer(&self.texture, buf, rect, device, encoder);
     }
 
-    fn blit(
+    fn fill_subresource(
+        &self,
+        buf: &[T],
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Texture::fill_subresource(&self.texture, buf, sub, origin, device, encoder);
+    }
+
+    fn transfer_subresource(
         &self,
-        from: Rect<u32>,
-        dst: Rect<u32>,
+        buf: &[T],
+        rect: Rect<i32>,
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
     ) {
-        Texture::blit(&self.texture, from, dst, encoder);
+        Texture::transfer_subresource(&self.texture, buf, rect, sub, origin, device, encoder);
+    }
+
+    fn transfer_strided(
+        &self,
+        buf: &[T],
+        stride: usize,
+        rect: Rect<i32>,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Texture::transfer_strided(&self.texture, buf, stride, rect, origin, device, encoder);
+    }
+
+    fn blit(&self, from: Rect<u32>, dst: Rect<u32>, encoder: &mut wgpu::CommandEncoder) {
+        Canvas::<T>::blit(&self.texture, from, dst, encoder);
     }
 }
@@ -1,12 +1,32 @@
+/// An integer type that can be stored in an `IndexBuffer`.
+pub trait IndexElement: bytemuck::Pod {
+    const FORMAT: wgpu::IndexFormat;
+}
+
+impl IndexElement for u16 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+}
+
+impl IndexElement for u32 {
+    const FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint32;
+}
+
 #[derive(Debug)]
 pub struct IndexBuffer {
     pub wgpu: wgpu::Buffer,
     pub elements: u32,
+    pub format: wgpu::IndexFormat,
 }
 
 impl IndexBuffer {
-    pub fn slice(&self) -> wgpu::BufferSlice {
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
         self.wgpu
-            .slice(0..(self.elements as usize * std::mem::size_of::<u16>()) as u64)
+            .slice(0..self.elements as u64 * self.format.byte_size() as u64)
+    }
+}
+
+impl AsRef<wgpu::Buffer> for IndexBuffer {
+    fn as_ref(&self) -> &wgpu::Buffer {
+        &self.wgpu
     }
 }
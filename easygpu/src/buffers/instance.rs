@@ -0,0 +1,192 @@
+use std::ops::{Index, IndexMut, Range};
+
+use crate::buffers::VertexBuffer;
+use crate::context::GpuContext;
+
+/// Per-instance vertex data kept on the CPU and mirrored into a vertex
+/// buffer.
+///
+/// Mutating elements marks them as dirty, and `upload` only writes the dirty
+/// spans, so updating a few instances out of many each frame is cheap. Bind
+/// `buffer()` to a slot whose step mode is `wgpu::VertexStepMode::Instance`.
+#[derive(Debug)]
+pub struct InstanceBuffer<T> {
+    buffer: VertexBuffer,
+    instances: Vec<T>,
+    dirty: DirtyRanges,
+}
+
+impl<T: bytemuck::Pod> InstanceBuffer<T> {
+    /// Creates a buffer holding `instances`, which are uploaded immediately.
+    ///
+    /// Panics if the size of `T` isn't a multiple of
+    /// `wgpu::COPY_BUFFER_ALIGNMENT`, since partial updates couldn't be
+    /// aligned.
+    pub fn new(device: &GpuContext, instances: Vec<T>) -> Self {
+        assert!(
+            (std::mem::size_of::<T>() as u64).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "instance size must be a multiple of {} bytes",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+        let wgpu = device.create_buffer_from_slice(
+            &instances,
+            wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        );
+        Self {
+            buffer: VertexBuffer {
+                size: std::mem::size_of_val(instances.as_slice()) as u32,
+                wgpu,
+            },
+            instances,
+            dirty: DirtyRanges::default(),
+        }
+    }
+
+    pub fn buffer(&self) -> &VertexBuffer {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.instances
+    }
+
+    /// Mutably borrows `range` of instances, marking them as dirty.
+    pub fn slice_mut(&mut self, range: Range<usize>) -> &mut [T] {
+        // Slice first, so an out of bounds range panics before it is marked.
+        let instances = &mut self.instances[range.clone()];
+        self.dirty.mark(range);
+        instances
+    }
+
+    /// The spans of instances that will be written by the next `upload`.
+    pub fn dirty_ranges(&self) -> &[Range<usize>] {
+        &self.dirty.0
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.0.is_empty()
+    }
+
+    /// Writes every dirty span to the GPU. The writes are visible to the
+    /// next submission.
+    pub fn upload(&mut self, device: &GpuContext) {
+        let size = std::mem::size_of::<T>();
+        for range in self.dirty.0.drain(..) {
+            device.queue.write_buffer(
+                &self.buffer.wgpu,
+                (range.start * size) as wgpu::BufferAddress,
+                bytemuck::cast_slice(&self.instances[range]),
+            );
+        }
+    }
+}
+
+/// Sorted, non-overlapping, non-adjacent ranges of dirty instances.
+#[derive(Debug, Default)]
+struct DirtyRanges(Vec<Range<usize>>);
+
+impl DirtyRanges {
+    fn mark(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        // Merge with every range that overlaps or touches the new one.
+        let first = self.0.partition_point(|dirty| dirty.end < range.start);
+        let last = self.0.partition_point(|dirty| dirty.start <= range.end);
+        let merged = self.0[first..last].iter().fold(range, |merged, dirty| {
+            merged.start.min(dirty.start)..merged.end.max(dirty.end)
+        });
+        self.0.splice(first..last, [merged]);
+    }
+}
+
+impl<T> Index<usize> for InstanceBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        &self.instances[index]
+    }
+}
+
+impl<T: bytemuck::Pod> IndexMut<usize> for InstanceBuffer<T> {
+    /// Mutably borrows an instance, marking it as dirty.
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        assert!(index < self.instances.len(), "instance index out of bounds");
+        self.dirty.mark(index..index + 1);
+        &mut self.instances[index]
+    }
+}
+
+impl<T> AsRef<wgpu::Buffer> for InstanceBuffer<T> {
+    fn as_ref(&self) -> &wgpu::Buffer {
+        &self.buffer.wgpu
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The dirty ranges after marking `ranges`, as `(start, end)` pairs.
+    fn marked<const N: usize>(ranges: [Range<usize>; N]) -> Vec<(usize, usize)> {
+        let mut dirty = DirtyRanges::default();
+        for range in ranges {
+            dirty.mark(range);
+        }
+        pairs(&dirty)
+    }
+
+    fn pairs(dirty: &DirtyRanges) -> Vec<(usize, usize)> {
+        dirty
+            .0
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_ranges_merge() {
+        assert_eq!(marked([2..6, 4..9]), [(2, 9)]);
+        assert_eq!(marked([4..9, 2..6]), [(2, 9)]);
+        assert_eq!(marked([3..4, 0..10]), [(0, 10)]);
+    }
+
+    #[test]
+    fn adjacent_ranges_merge() {
+        assert_eq!(marked([0..3, 3..5]), [(0, 5)]);
+        assert_eq!(marked([3..5, 0..3]), [(0, 5)]);
+    }
+
+    #[test]
+    fn disjoint_ranges_stay_sorted() {
+        assert_eq!(marked([8..10, 0..2, 4..5]), [(0, 2), (4, 5), (8, 10)]);
+        // Filling the gaps joins all of them.
+        assert_eq!(marked([8..10, 0..2, 4..5, 2..4, 5..8]), [(0, 10)]);
+    }
+
+    #[test]
+    fn empty_ranges_are_ignored() {
+        assert_eq!(marked([3..3, 5..5]), []);
+        assert_eq!(marked([0..2, 4..4]), [(0, 2)]);
+    }
+
+    #[test]
+    fn draining_clears() {
+        let mut dirty = DirtyRanges::default();
+        dirty.mark(0..2);
+        dirty.mark(6..8);
+        assert_eq!(pairs(&dirty), [(0, 2), (6, 8)]);
+        assert_eq!(dirty.0.drain(..).count(), 2);
+        assert!(dirty.0.is_empty());
+        dirty.mark(1..3);
+        assert_eq!(pairs(&dirty), [(1, 3)]);
+    }
+}
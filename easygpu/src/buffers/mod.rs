@@ -1,11 +1,13 @@
 mod depth;
 mod frame;
 mod index;
+mod instance;
 mod uniform;
 mod vertex;
 
 pub use self::depth::*;
 pub use self::frame::*;
 pub use self::index::*;
+pub use self::instance::*;
 pub use self::uniform::*;
 pub use self::vertex::*;
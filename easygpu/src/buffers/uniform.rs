@@ -1,6 +1,9 @@
 use std::num::NonZeroU64;
 
 use crate::binding::Bind;
+use crate::error::ResourceError;
+
+use super::vertex::check_buffer;
 
 /// A uniform buffer that can be bound in a 'BindingGroup'.
 #[derive(Debug)]
@@ -10,8 +13,26 @@ pub struct UniformBuffer {
     pub count: usize,
 }
 
+impl UniformBuffer {
+    /// Wraps a buffer created outside of easygpu holding `count` elements of
+    /// `size` bytes. The buffer must have `UNIFORM` usage, and also `COPY_DST`
+    /// usage to be updated with `Device::update_uniform_buffer`.
+    pub fn from_wgpu(
+        buffer: wgpu::Buffer,
+        size: usize,
+        count: usize,
+    ) -> Result<Self, ResourceError> {
+        check_buffer(&buffer, wgpu::BufferUsages::UNIFORM, (size * count) as u64)?;
+        Ok(Self {
+            wgpu: buffer,
+            size,
+            count,
+        })
+    }
+}
+
 impl Bind for UniformBuffer {
-    fn binding(&self, index: u32) -> wgpu::BindGroupEntry {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding: index,
             resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
@@ -22,3 +43,9 @@ impl Bind for UniformBuffer {
         }
     }
 }
+
+impl AsRef<wgpu::Buffer> for UniformBuffer {
+    fn as_ref(&self) -> &wgpu::Buffer {
+        &self.wgpu
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::ResourceError;
 use crate::prelude::BindingGroup;
 use crate::renderer::{Draw, RenderPassExt};
 
@@ -21,7 +22,42 @@ impl Draw for VertexBuffer {
 }
 
 impl VertexBuffer {
-    pub fn slice(&self) -> wgpu::BufferSlice {
+    /// Wraps a buffer created outside of easygpu. `size` is the number of
+    /// bytes of vertex data, and the buffer must have `VERTEX` usage.
+    pub fn from_wgpu(buffer: wgpu::Buffer, size: u32) -> Result<Self, ResourceError> {
+        check_buffer(&buffer, wgpu::BufferUsages::VERTEX, u64::from(size))?;
+        Ok(Self { size, wgpu: buffer })
+    }
+
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
         self.wgpu.slice(0..self.size as u64)
     }
 }
+
+impl AsRef<wgpu::Buffer> for VertexBuffer {
+    fn as_ref(&self) -> &wgpu::Buffer {
+        &self.wgpu
+    }
+}
+
+/// Checks that a buffer created outside of easygpu has the `required` usages
+/// and holds at least `size` bytes.
+pub(crate) fn check_buffer(
+    buffer: &wgpu::Buffer,
+    required: wgpu::BufferUsages,
+    size: u64,
+) -> Result<(), ResourceError> {
+    if !buffer.usage().contains(required) {
+        return Err(ResourceError::MissingBufferUsage {
+            required,
+            actual: buffer.usage(),
+        });
+    }
+    if buffer.size() < size {
+        return Err(ResourceError::BufferTooSmall {
+            required: size,
+            actual: buffer.size(),
+        });
+    }
+    Ok(())
+}
@@ -1,23 +1,290 @@
+use std::borrow::Cow;
+
 use figures::Rect;
+use wgpu::TextureFormat;
 
+use crate::color::{Bgra8, ColorSpace, Rgba, Rgba8};
 use crate::device::Device;
+use crate::texture::{Origin, SubResource};
+
+/// A type whose values can be copied directly into the texels of a texture.
+pub trait Texel: bytemuck::Pod {
+    /// Returns true if a value of this type has the layout of a single texel
+    /// of `format`, and textures of `format` can be written by buffer copies.
+    ///
+    /// Depth formats never match: clear them with `DepthBuffer::clear`
+    /// instead.
+    fn matches(format: wgpu::TextureFormat) -> bool;
+
+    /// Converts a color written in `color_space` to the sRGB encoding stored
+    /// in textures. Types that aren't colors are returned unchanged.
+    fn encode(self, color_space: ColorSpace) -> Self {
+        let _ = color_space;
+        self
+    }
+}
+
+impl Texel for Rgba8 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        )
+    }
+
+    fn encode(self, color_space: ColorSpace) -> Self {
+        color_space.to_encoded(Rgba::from(self)).into()
+    }
+}
+
+impl Texel for Bgra8 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+        )
+    }
+
+    fn encode(self, color_space: ColorSpace) -> Self {
+        Rgba8::from(self).encode(color_space).into()
+    }
+}
+
+/// Raw bytes, which match any format with single-byte texels. This is an
+/// escape hatch for formats without a dedicated texel type; the bytes are
+/// copied as-is.
+impl Texel for u8 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        has_texel_size::<Self>(format)
+    }
+}
+
+/// Raw 4-byte texels, which match any format with 4-byte texels, including
+/// packed formats such as `Rgb10a2Unorm`. This is an escape hatch for formats
+/// without a dedicated texel type; the bytes are copied as-is.
+impl Texel for u32 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        has_texel_size::<Self>(format)
+    }
+}
+
+impl Texel for u16 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::R16Uint
+                | TextureFormat::R16Sint
+                | TextureFormat::R16Unorm
+                | TextureFormat::R16Snorm
+                | TextureFormat::R16Float
+        )
+    }
+}
+
+impl Texel for f32 {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        format == TextureFormat::R32Float
+    }
+}
+
+impl Texel for [u8; 2] {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::Rg8Unorm
+                | TextureFormat::Rg8Snorm
+                | TextureFormat::Rg8Uint
+                | TextureFormat::Rg8Sint
+        )
+    }
+}
+
+/// The channels of any 8-bit four channel format, in the format's order.
+impl Texel for [u8; 4] {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        Rgba8::matches(format)
+            || Bgra8::matches(format)
+            || matches!(
+                format,
+                TextureFormat::Rgba8Snorm | TextureFormat::Rgba8Uint | TextureFormat::Rgba8Sint
+            )
+    }
+}
+
+impl Texel for [u16; 4] {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        matches!(
+            format,
+            TextureFormat::Rgba16Uint
+                | TextureFormat::Rgba16Sint
+                | TextureFormat::Rgba16Unorm
+                | TextureFormat::Rgba16Snorm
+                | TextureFormat::Rgba16Float
+        )
+    }
+}
+
+impl Texel for [f32; 2] {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        format == TextureFormat::Rg32Float
+    }
+}
+
+impl Texel for [f32; 4] {
+    fn matches(format: wgpu::TextureFormat) -> bool {
+        format == TextureFormat::Rgba32Float
+    }
+}
+
+/// Returns true if `format` is an uncompressed color format whose texels are
+/// the size of `T`.
+fn has_texel_size<T>(format: wgpu::TextureFormat) -> bool {
+    !format.is_depth_stencil_format()
+        && format.block_dimensions() == (1, 1)
+        && format.block_copy_size(None) == Some(std::mem::size_of::<T>() as u32)
+}
 
-pub trait Canvas {
-    type Color;
+/// Encodes `texels` for `color_space`, borrowing them when no conversion is
+/// needed.
+pub(crate) fn encode_texels<T: Texel>(texels: &[T], color_space: ColorSpace) -> Cow<'_, [T]> {
+    match color_space {
+        ColorSpace::Srgb => Cow::Borrowed(texels),
+        ColorSpace::Linear => Cow::Owned(
+            texels
+                .iter()
+                .map(|texel| texel.encode(color_space))
+                .collect(),
+        ),
+    }
+}
 
-    fn clear(&self, color: Self::Color, device: &mut Device, encoder: &mut wgpu::CommandEncoder);
-    fn fill(&self, buf: &[Self::Color], device: &mut Device, encoder: &mut wgpu::CommandEncoder);
+/// A texture that can be written with texels of type `T`.
+///
+/// Each operation panics if `T` doesn't match the texture's format. Color
+/// texels are converted from the device's `ColorSpace`.
+pub trait Canvas<T: Texel> {
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder);
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder);
     fn transfer(
         &self,
-        buf: &[Self::Color],
+        buf: &[T],
         r: Rect<i32>,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
     );
-    fn blit(
+    fn blit(&self, from: Rect<u32>, dst: Rect<u32>, encoder: &mut wgpu::CommandEncoder);
+
+    /// Fills a single mip level of a single array layer, with rows ordered
+    /// according to `origin`. The default implementation only supports the
+    /// base subresource in the `Origin::TopLeft` order of `fill`.
+    fn fill_subresource(
         &self,
-        from: Rect<u32>,
-        dst: Rect<u32>,
+        buf: &[T],
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
-    );
+    ) {
+        assert_eq!(
+            sub,
+            SubResource::default(),
+            "this canvas only has a single subresource"
+        );
+        assert_eq!(origin, Origin::TopLeft, "this canvas only fills top-down");
+        self.fill(buf, device, encoder);
+    }
+
+    /// Transfers into a single mip level of a single array layer, measuring
+    /// `r` from `origin`. The default implementation only supports the base
+    /// subresource in the `Origin::TopLeft` order of `transfer`.
+    fn transfer_subresource(
+        &self,
+        buf: &[T],
+        r: Rect<i32>,
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        assert_eq!(
+            sub,
+            SubResource::default(),
+            "this canvas only has a single subresource"
+        );
+        assert_eq!(
+            origin,
+            Origin::TopLeft,
+            "this canvas only transfers top-down"
+        );
+        self.transfer(buf, r, device, encoder);
+    }
+
+    /// Transfers into the base subresource like `transfer`, measuring `r`
+    /// from `origin` and taking rows in its order.
+    fn transfer_with_origin(
+        &self,
+        buf: &[T],
+        r: Rect<i32>,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        self.transfer_subresource(buf, r, SubResource::default(), origin, device, encoder);
+    }
+
+    /// Transfers a rect of texels out of a larger image whose rows are
+    /// `stride` texels apart, measuring `r` from `origin`. See
+    /// `Texture::transfer_strided`. The default implementation packs the rows
+    /// and calls `transfer_subresource`.
+    fn transfer_strided(
+        &self,
+        buf: &[T],
+        stride: usize,
+        r: Rect<i32>,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let size = Rect::from_extents(r.origin, r.extent()).size;
+        let width = size.width as usize;
+        let packed = (0..size.height as usize)
+            .flat_map(|row| &buf[row * stride..][..width])
+            .copied()
+            .collect::<Vec<_>>();
+        self.transfer_subresource(&packed, r, SubResource::default(), origin, device, encoder);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_match_their_channel_order() {
+        assert!(Rgba8::matches(TextureFormat::Rgba8UnormSrgb));
+        assert!(!Rgba8::matches(TextureFormat::Bgra8UnormSrgb));
+        assert!(Bgra8::matches(TextureFormat::Bgra8Unorm));
+        assert!(!Bgra8::matches(TextureFormat::Rgba8Unorm));
+        assert!(!Rgba8::matches(TextureFormat::R32Float));
+    }
+
+    #[test]
+    fn typed_texels_match_their_family() {
+        assert!(f32::matches(TextureFormat::R32Float));
+        assert!(!f32::matches(TextureFormat::R32Uint));
+        assert!(!f32::matches(TextureFormat::Rgba8Unorm));
+        assert!(<[u16; 4]>::matches(TextureFormat::Rgba16Float));
+        assert!(!<[u16; 4]>::matches(TextureFormat::Rg32Float));
+        assert!(<[u8; 4]>::matches(TextureFormat::Bgra8UnormSrgb));
+    }
+
+    #[test]
+    fn raw_texels_match_by_size() {
+        assert!(u32::matches(TextureFormat::Rgba8Unorm));
+        assert!(u32::matches(TextureFormat::R32Float));
+        assert!(!u32::matches(TextureFormat::Rgba16Float));
+        assert!(u8::matches(TextureFormat::R8Unorm));
+        assert!(!u32::matches(TextureFormat::Depth32Float));
+        assert!(!u8::matches(TextureFormat::Bc1RgbaUnorm));
+    }
 }
@@ -107,6 +107,69 @@ impl Rgba {
     pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
+
+    /// Decodes sRGB-encoded color channels to linear. Alpha is unchanged.
+    pub fn to_linear(self) -> Self {
+        fn linearize(c: f32) -> f32 {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+        Self::new(
+            linearize(self.r),
+            linearize(self.g),
+            linearize(self.b),
+            self.a,
+        )
+    }
+
+    /// Encodes linear color channels as sRGB. Alpha is unchanged.
+    pub fn to_srgb(self) -> Self {
+        fn encode(c: f32) -> f32 {
+            if c <= 0.003_130_8 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1. / 2.4) - 0.055
+            }
+        }
+        Self::new(encode(self.r), encode(self.g), encode(self.b), self.a)
+    }
+}
+
+/// The color space that colors given to easygpu are written in, including
+/// clear colors, the texels of color `Canvas` writes, and lyon vertex colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Colors are sRGB-encoded, like those picked from an image editor.
+    #[default]
+    Srgb,
+    /// Colors are linear, like the results of lighting calculations.
+    Linear,
+}
+
+impl ColorSpace {
+    /// Converts `color` to the value a pass should clear a target of
+    /// `format` to. sRGB targets expect linear values and encode them when
+    /// stored, while other targets store values unchanged and are displayed
+    /// as if they were sRGB-encoded.
+    pub fn to_target(self, color: Rgba, format: wgpu::TextureFormat) -> Rgba {
+        match (self, format.is_srgb()) {
+            (ColorSpace::Srgb, true) => color.to_linear(),
+            (ColorSpace::Linear, false) => color.to_srgb(),
+            _ => color,
+        }
+    }
+
+    /// Converts `color` to sRGB encoding, which is how 8-bit color texels
+    /// and vertex colors are stored.
+    pub fn to_encoded(self, color: Rgba) -> Rgba {
+        match self {
+            ColorSpace::Srgb => color,
+            ColorSpace::Linear => color.to_srgb(),
+        }
+    }
 }
 
 impl From<Bgra8> for Rgba8 {
@@ -0,0 +1,1102 @@
+//! The shareable core of a `Device`: the wgpu device and queue, along with
+//! the settings every resource is created with.
+
+use figures::Size;
+use wgpu::util::DeviceExt;
+use wgpu::{
+    FilterMode, MultisampleState, StorageTextureAccess, SubmissionIndex, TextureFormat,
+    TextureFormatFeatureFlags, TextureUsages,
+};
+
+use crate::binding::{Bind, Binding, BindingGroup, BindingGroupLayout};
+use crate::blending::Blending;
+use crate::buffers::{
+    DepthBuffer, DepthMode, Framebuffer, IndexBuffer, IndexElement, InstanceBuffer, UniformBuffer,
+    VertexBuffer,
+};
+use crate::error::PipelineError;
+use crate::mesh::Mesh;
+use crate::picking::IdBuffer;
+use crate::pipeline::{
+    self, AbstractPipeline, ComputePipeline, DynamicPipeline, DynamicPipelineDescription, Pipeline,
+    PipelineLayout, PipelineVariants, Set, StencilMode,
+};
+use crate::query::{self, StatisticsQuery};
+use crate::sampler::Sampler;
+use crate::shader::Shader;
+use crate::texture::Texture;
+use crate::vertex::VertexLayout;
+
+/// The wgpu device and queue shared by a `Device` and every clone of its
+/// `Arc<GpuContext>`.
+///
+/// All of its methods take `&self`, so resources can be created from any
+/// thread, such as asset loaders running in the background. Get one with
+/// `Device::context` or `Renderer::context`.
+#[derive(Debug)]
+pub struct GpuContext {
+    pub wgpu: wgpu::Device,
+    pub queue: wgpu::Queue,
+    depth_format: TextureFormat,
+    depth_mode: DepthMode,
+    /// Enables MSAA for values > 1.
+    pub(crate) sample_count: u32,
+}
+
+impl GpuContext {
+    pub(crate) const fn new(
+        wgpu: wgpu::Device,
+        queue: wgpu::Queue,
+        depth_format: TextureFormat,
+        depth_mode: DepthMode,
+        sample_count: u32,
+    ) -> Self {
+        Self {
+            wgpu,
+            queue,
+            depth_format,
+            depth_mode,
+            sample_count,
+        }
+    }
+
+    pub const fn device(&self) -> &wgpu::Device {
+        &self.wgpu
+    }
+
+    pub const fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// The optional wgpu features enabled on this device.
+    pub fn features(&self) -> wgpu::Features {
+        self.wgpu.features()
+    }
+
+    /// The format of depth buffers and pipeline depth states created by this
+    /// device.
+    pub const fn depth_format(&self) -> TextureFormat {
+        self.depth_format
+    }
+
+    pub const fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    /// The sample count used by `texture`, `framebuffer`, `zbuffer`, and
+    /// pipelines. MSAA is enabled for values > 1.
+    pub const fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn create_command_encoder(&self) -> wgpu::CommandEncoder {
+        self.wgpu
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
+    }
+
+    pub fn create_pipeline_layout(&self, ss: &[Set]) -> PipelineLayout {
+        let mut sets = Vec::new();
+        for (i, s) in ss.iter().enumerate() {
+            sets.push(self.create_binding_group_layout(i as u32, s.0));
+        }
+        PipelineLayout { sets }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(bytes = source.len()))
+    )]
+    pub fn create_shader(&self, source: &str) -> Shader {
+        Shader {
+            wgpu: self
+                .wgpu
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    source: wgpu::ShaderSource::Wgsl(source.into()),
+                    label: None, // TODO labels would be nice
+                }),
+        }
+    }
+
+    pub fn create_texture(
+        &self,
+        size: Size<u32>,
+        format: TextureFormat,
+        usage: TextureUsages,
+        sample_count: u32,
+    ) -> Texture {
+        self.create_texture_with_view_formats(size, format, usage, sample_count, &[])
+    }
+
+    /// Creates a texture that can also be viewed as any of `view_formats`
+    /// using `Texture::view_as`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                ?format,
+                sample_count = sample_count,
+            )
+        )
+    )]
+    pub fn create_texture_with_view_formats(
+        &self,
+        size: Size<u32>,
+        format: TextureFormat,
+        usage: TextureUsages,
+        sample_count: u32,
+        view_formats: &[TextureFormat],
+    ) -> Texture {
+        let texture_extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.wgpu.create_texture(&wgpu::TextureDescriptor {
+            size: texture_extent,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            label: None,
+            view_formats,
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Texture {
+            wgpu: texture,
+            view: texture_view,
+            extent: texture_extent,
+            format,
+            size,
+        }
+    }
+
+    /// Creates a single-sample texture with `layers` array layers and
+    /// `mip_level_count` mip levels. Use `Texture::create_view` to access the
+    /// individual levels and layers.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                layers = layers,
+                mip_level_count = mip_level_count,
+                ?format,
+            )
+        )
+    )]
+    pub fn create_texture_layers(
+        &self,
+        size: Size<u32>,
+        layers: u32,
+        mip_level_count: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+    ) -> Texture {
+        let texture_extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: layers,
+        };
+        let texture = self.wgpu.create_texture(&wgpu::TextureDescriptor {
+            size: texture_extent,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            label: None,
+            view_formats: &[],
+        });
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Texture {
+            wgpu: texture,
+            view: texture_view,
+            extent: texture_extent,
+            format,
+            size,
+        }
+    }
+
+    /// Creates a texture that shaders can access directly through a
+    /// `BindingType::StorageTexture` slot with `access`, such as to write an
+    /// image from a compute shader. The texture can also be sampled and
+    /// copied.
+    ///
+    /// Panics if `format` doesn't support that access, unless the device has
+    /// `wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`, in which
+    /// case support depends on the adapter.
+    pub fn create_storage_texture(
+        &self,
+        size: Size<u32>,
+        format: TextureFormat,
+        access: StorageTextureAccess,
+    ) -> Texture {
+        let required = match access {
+            StorageTextureAccess::WriteOnly => TextureFormatFeatureFlags::STORAGE_WRITE_ONLY,
+            StorageTextureAccess::ReadOnly => TextureFormatFeatureFlags::STORAGE_READ_ONLY,
+            StorageTextureAccess::ReadWrite => TextureFormatFeatureFlags::STORAGE_READ_WRITE,
+            StorageTextureAccess::Atomic => TextureFormatFeatureFlags::STORAGE_ATOMIC,
+        };
+        let features = format.guaranteed_format_features(self.features());
+        assert!(
+            features.flags.contains(required)
+                || self
+                    .features()
+                    .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
+            "{format:?} textures don't support {access:?} storage access"
+        );
+
+        self.create_texture(
+            size,
+            format,
+            TextureUsages::STORAGE_BINDING
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST,
+            1,
+        )
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                ?format,
+                sample_count = sample_count,
+            )
+        )
+    )]
+    pub fn create_framebuffer(
+        &self,
+        size: Size<u32>,
+        format: TextureFormat,
+        sample_count: u32,
+    ) -> Framebuffer {
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = self.wgpu.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::COPY_SRC
+                | TextureUsages::RENDER_ATTACHMENT,
+            label: None,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Framebuffer {
+            texture: Texture {
+                wgpu: texture,
+                view,
+                extent,
+                format,
+                size,
+            },
+            depth: self.create_zbuffer(size, sample_count),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                sample_count = sample_count,
+            )
+        )
+    )]
+    pub fn create_zbuffer(&self, size: Size<u32>, sample_count: u32) -> DepthBuffer {
+        let format = self.depth_format;
+        let extent = wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        };
+        let wgpu = self.wgpu.create_texture(&wgpu::TextureDescriptor {
+            size: extent,
+            label: None,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = wgpu.create_view(&wgpu::TextureViewDescriptor::default());
+
+        DepthBuffer {
+            texture: Texture {
+                wgpu,
+                view,
+                extent,
+                format,
+                size,
+            },
+        }
+    }
+
+    pub fn create_binding_group(
+        &self,
+        layout: &BindingGroupLayout,
+        binds: &[&dyn Bind],
+    ) -> BindingGroup {
+        assert_eq!(
+            binds.len(),
+            layout.size,
+            "layout slot count does not match bindings"
+        );
+
+        let mut bindings = Vec::new();
+
+        for (i, b) in binds.iter().enumerate() {
+            bindings.push(b.binding(i as u32));
+        }
+
+        BindingGroup::new(
+            layout.set_index,
+            self.wgpu.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &layout.wgpu,
+                label: None,
+                entries: bindings.as_slice(),
+            }),
+        )
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                bytes = std::mem::size_of_val(vertices),
+            )
+        )
+    )]
+    pub fn create_buffer<T>(&self, vertices: &[T]) -> VertexBuffer
+    where
+        T: bytemuck::Pod + 'static + Copy,
+    {
+        VertexBuffer {
+            wgpu: self.create_buffer_from_slice(vertices, wgpu::BufferUsages::VERTEX),
+            size: std::mem::size_of_val(vertices) as u32,
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(bytes = std::mem::size_of_val(buf)))
+    )]
+    pub fn create_uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: bytemuck::Pod + 'static + Copy,
+    {
+        UniformBuffer {
+            size: std::mem::size_of::<T>(),
+            count: buf.len(),
+            wgpu: self
+                .wgpu
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Uniform Buffer"),
+                    contents: bytemuck::cast_slice(buf),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                }),
+        }
+    }
+
+    /// Creates `count` pipeline statistics queries, or returns `None` if the
+    /// device wasn't created with `wgpu::Features::PIPELINE_STATISTICS_QUERY`.
+    pub fn create_statistics_query(&self, count: u32) -> Option<StatisticsQuery> {
+        if !self
+            .features()
+            .contains(wgpu::Features::PIPELINE_STATISTICS_QUERY)
+        {
+            return None;
+        }
+
+        let wgpu = self.wgpu.create_query_set(&wgpu::QuerySetDescriptor {
+            label: None,
+            ty: wgpu::QueryType::PipelineStatistics(query::STATISTICS),
+            count,
+        });
+        let size = u64::from(count) * query::RESULT_SIZE;
+        let resolve = self.wgpu.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = self.wgpu.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(StatisticsQuery {
+            wgpu,
+            resolve,
+            readback,
+            count,
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(count = indices.len()))
+    )]
+    pub fn create_index<I: IndexElement>(&self, indices: &[I]) -> IndexBuffer {
+        let index_buf = self.create_buffer_from_slice(indices, wgpu::BufferUsages::INDEX);
+        IndexBuffer {
+            wgpu: index_buf,
+            elements: indices.len() as u32,
+            format: I::FORMAT,
+        }
+    }
+
+    pub fn create_sampler(&self, min_filter: FilterMode, mag_filter: FilterMode) -> Sampler {
+        Sampler {
+            wgpu: self.wgpu.create_sampler(&wgpu::SamplerDescriptor {
+                address_mode_u: wgpu::AddressMode::ClampToEdge,
+                address_mode_v: wgpu::AddressMode::ClampToEdge,
+                address_mode_w: wgpu::AddressMode::ClampToEdge,
+                mag_filter,
+                min_filter,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                lod_min_clamp: 0.,
+                lod_max_clamp: 100.0,
+                compare: None,
+                anisotropy_clamp: 1,
+                label: None,
+                border_color: None,
+            }),
+        }
+    }
+
+    pub fn create_binding_group_layout(&self, index: u32, slots: &[Binding]) -> BindingGroupLayout {
+        let mut bindings = Vec::new();
+
+        for s in slots {
+            bindings.push(wgpu::BindGroupLayoutEntry {
+                binding: bindings.len() as u32,
+                visibility: s.stage,
+                ty: s.binding.into(),
+                count: None,
+            });
+        }
+        let layout = self
+            .wgpu
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: bindings.as_slice(),
+            });
+        BindingGroupLayout::new(index, layout, slots.iter().map(|s| s.binding).collect())
+    }
+
+    pub fn create_buffer_from_slice<T: bytemuck::Pod>(
+        &self,
+        slice: &[T],
+        usage: wgpu::BufferUsages,
+    ) -> wgpu::Buffer {
+        self.wgpu
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(slice),
+                usage,
+            })
+    }
+
+    pub fn update_uniform_buffer<T: bytemuck::Pod + Copy + 'static>(
+        &self,
+        slice: &[T],
+        buf: &UniformBuffer,
+    ) {
+        self.update_uniform_buffer_at(slice, buf, 0);
+    }
+
+    /// Writes `slice` into `buf` starting at the element `element_offset`,
+    /// leaving the other elements unchanged.
+    ///
+    /// Panics if `T` is not the buffer's element type or if the elements would
+    /// extend past the end of the buffer.
+    pub fn update_uniform_buffer_at<T: bytemuck::Pod + Copy + 'static>(
+        &self,
+        slice: &[T],
+        buf: &UniformBuffer,
+        element_offset: usize,
+    ) {
+        assert_eq!(
+            std::mem::size_of::<T>(),
+            buf.size,
+            "uniform element size does not match buffer"
+        );
+        assert!(
+            element_offset + slice.len() <= buf.count,
+            "uniform update of {} elements at {element_offset} exceeds buffer of {} elements",
+            slice.len(),
+            buf.count
+        );
+        self.queue.write_buffer(
+            &buf.wgpu,
+            (element_offset * buf.size) as wgpu::BufferAddress,
+            bytemuck::cast_slice(slice),
+        );
+    }
+
+    /// Submits command buffers to the queue. The queue is internally
+    /// synchronized, so this can be called from any thread.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(&self, cmds: I) -> SubmissionIndex {
+        self.queue.submit(cmds)
+    }
+
+    /// Creates a compute pipeline running the `cs_main` entry point of
+    /// `shader`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(sets = pipeline_layout.sets.len()))
+    )]
+    pub fn create_compute_pipeline(
+        &self,
+        pipeline_layout: PipelineLayout,
+        shader: &Shader,
+    ) -> ComputePipeline {
+        let sets = pipeline_layout
+            .sets
+            .iter()
+            .map(|set| &set.wgpu)
+            .collect::<Vec<_>>();
+        let layout = self
+            .wgpu
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &sets,
+                push_constant_ranges: &[],
+            });
+        let wgpu = self
+            .wgpu
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&layout),
+                module: &shader.wgpu,
+                entry_point: Some("cs_main"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: None,
+            });
+
+        ComputePipeline {
+            wgpu,
+            layout: pipeline_layout,
+        }
+    }
+
+    /// Creates a pipeline drawing to color targets of `target_format`, which
+    /// is independent of the surface's format, such as `Rgba8Unorm` for an
+    /// offscreen framebuffer while the surface is `Bgra8UnormSrgb`.
+    pub fn create_pipeline(
+        &self,
+        pipeline_layout: PipelineLayout,
+        vertex_layouts: Vec<VertexLayout>,
+        blending: Blending,
+        shader: &Shader,
+        target_format: TextureFormat,
+        multisample: MultisampleState,
+    ) -> Pipeline {
+        let wgpu = self.create_render_pipeline(
+            &pipeline_layout,
+            &vertex_layouts,
+            blending,
+            shader,
+            target_format,
+            multisample,
+        );
+
+        Pipeline {
+            layout: pipeline_layout,
+            vertex_layouts,
+            format: target_format,
+            wgpu,
+        }
+    }
+
+    /// Creates the `wgpu::RenderPipeline` of a `Pipeline` without taking
+    /// ownership of its layouts, so several pipelines can share the same bind
+    /// group layouts.
+    pub fn create_render_pipeline(
+        &self,
+        pipeline_layout: &PipelineLayout,
+        vertex_layouts: &[VertexLayout],
+        blending: Blending,
+        shader: &Shader,
+        target_format: TextureFormat,
+        multisample: MultisampleState,
+    ) -> wgpu::RenderPipeline {
+        self.create_render_pipeline_with_targets(
+            pipeline_layout,
+            vertex_layouts,
+            blending,
+            shader,
+            target_format,
+            multisample,
+            &[],
+            StencilMode::Ignore,
+        )
+    }
+
+    /// Creates a `wgpu::RenderPipeline` like `create_render_pipeline` that
+    /// uses the stencil buffer as described by `stencil`. Panics if `stencil`
+    /// isn't `StencilMode::Ignore` and the depth format has no stencil aspect.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_stencil_pipeline(
+        &self,
+        pipeline_layout: &PipelineLayout,
+        vertex_layouts: &[VertexLayout],
+        blending: Blending,
+        shader: &Shader,
+        target_format: TextureFormat,
+        multisample: MultisampleState,
+        stencil: StencilMode,
+    ) -> wgpu::RenderPipeline {
+        assert!(
+            stencil == StencilMode::Ignore || self.depth_format.has_stencil_aspect(),
+            "{stencil:?} requires a depth format with a stencil aspect, but the device uses {:?}",
+            self.depth_format
+        );
+        self.create_render_pipeline_with_targets(
+            pipeline_layout,
+            vertex_layouts,
+            blending,
+            shader,
+            target_format,
+            multisample,
+            &[],
+            stencil,
+        )
+    }
+
+    /// Creates a pipeline like `create_pipeline` whose fragment shader also
+    /// writes a `u32` object id to `@location(1)`, for drawing in passes begun
+    /// with `Frame::pass_with_ids`.
+    ///
+    /// Picking pipelines are never multisampled, because integer targets
+    /// can't be resolved.
+    pub fn create_picking_pipeline(
+        &self,
+        pipeline_layout: PipelineLayout,
+        vertex_layouts: Vec<VertexLayout>,
+        blending: Blending,
+        shader: &Shader,
+        target_format: TextureFormat,
+    ) -> Pipeline {
+        let wgpu = self.create_render_pipeline_with_targets(
+            &pipeline_layout,
+            &vertex_layouts,
+            blending,
+            shader,
+            target_format,
+            MultisampleState::default(),
+            &[Some(wgpu::ColorTargetState {
+                format: IdBuffer::FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            StencilMode::Ignore,
+        );
+
+        Pipeline {
+            layout: pipeline_layout,
+            vertex_layouts,
+            format: target_format,
+            wgpu,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                format = ?target_format,
+                samples = multisample.count,
+                ?stencil,
+            )
+        )
+    )]
+    fn create_render_pipeline_with_targets(
+        &self,
+        pipeline_layout: &PipelineLayout,
+        vertex_layouts: &[VertexLayout],
+        blending: Blending,
+        shader: &Shader,
+        target_format: TextureFormat,
+        multisample: MultisampleState,
+        extra_targets: &[Option<wgpu::ColorTargetState>],
+        stencil: StencilMode,
+    ) -> wgpu::RenderPipeline {
+        let vertex_buffers = vertex_layouts
+            .iter()
+            .map(wgpu::VertexBufferLayout::from)
+            .collect::<Vec<_>>();
+
+        let mut sets = Vec::new();
+        for s in pipeline_layout.sets.iter() {
+            sets.push(&s.wgpu);
+        }
+        let layout = &self
+            .wgpu
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: sets.as_slice(),
+                push_constant_ranges: &[],
+            });
+
+        let (src_factor, dst_factor, operation) = blending.as_wgpu();
+        let mut targets = vec![Some(wgpu::ColorTargetState {
+            format: target_format,
+            blend: Some(wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor,
+                    dst_factor,
+                    operation,
+                },
+                alpha: wgpu::BlendComponent {
+                    src_factor,
+                    dst_factor,
+                    operation,
+                },
+            }),
+
+            write_mask: if stencil.is_stencil_only() {
+                wgpu::ColorWrites::empty()
+            } else {
+                wgpu::ColorWrites::ALL
+            },
+        })];
+        targets.extend_from_slice(extra_targets);
+
+        self.wgpu
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module: &shader.wgpu,
+                    entry_point: Some("vs_main"),
+                    buffers: vertex_buffers.as_slice(),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: self.depth_format,
+                    depth_write_enabled: !stencil.is_stencil_only(),
+                    depth_compare: if stencil.is_stencil_only() {
+                        wgpu::CompareFunction::Always
+                    } else {
+                        self.depth_mode.compare()
+                    },
+                    stencil: stencil.state(),
+                    bias: wgpu::DepthBiasState {
+                        constant: 0,
+                        slope_scale: 0.,
+                        clamp: 0.,
+                    },
+                }),
+                multisample,
+                multiview: None,
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader.wgpu,
+                    entry_point: Some("fs_main"),
+                    targets: &targets,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                }),
+                cache: None,
+            })
+    }
+
+    pub fn texture(
+        &self,
+        size: Size<u32>,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        multisampled: bool,
+    ) -> Texture {
+        self.texture_with_view_formats(size, format, usage, multisampled, &[])
+    }
+
+    pub fn texture_with_view_formats(
+        &self,
+        size: Size<u32>,
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        multisampled: bool,
+        view_formats: &[wgpu::TextureFormat],
+    ) -> Texture {
+        let sample_count = if multisampled { self.sample_count } else { 1 };
+        self.create_texture_with_view_formats(size, format, usage, sample_count, view_formats)
+    }
+
+    pub fn framebuffer(&self, size: Size<u32>, format: wgpu::TextureFormat) -> Framebuffer {
+        self.create_framebuffer(size, format, self.sample_count)
+    }
+
+    pub fn zbuffer(&self, size: Size<u32>) -> DepthBuffer {
+        self.create_zbuffer(size, self.sample_count)
+    }
+
+    pub fn vertex_buffer<T>(&self, verts: &[T]) -> VertexBuffer
+    where
+        T: bytemuck::Pod + 'static + Copy,
+    {
+        self.create_buffer(verts)
+    }
+
+    pub fn instance_buffer<T>(&self, instances: Vec<T>) -> InstanceBuffer<T>
+    where
+        T: bytemuck::Pod,
+    {
+        InstanceBuffer::new(self, instances)
+    }
+
+    pub fn mesh<V, I>(&self, vertices: &[V], indices: &[I]) -> Mesh
+    where
+        V: bytemuck::Pod + 'static + Copy,
+        I: IndexElement,
+    {
+        Mesh::from_slices(self, vertices, indices)
+    }
+
+    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
+    where
+        T: bytemuck::Pod + 'static + Copy,
+    {
+        self.create_uniform_buffer(buf)
+    }
+
+    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
+        self.create_binding_group(layout, binds)
+    }
+
+    pub fn sampler(&self, min_filter: FilterMode, mag_filter: FilterMode) -> Sampler {
+        self.create_sampler(min_filter, mag_filter)
+    }
+
+    /// Builds `T` for color targets of `format`, which doesn't need to match
+    /// the surface. Use `Frame::pass_for` to check that a pass's target
+    /// matches.
+    pub fn pipeline<T>(&self, blending: Blending, format: TextureFormat) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let pip_layout = self.create_pipeline_layout(desc.pipeline_layout);
+        let vertex_layouts = VertexLayout::from_buffers(desc.vertex_layout);
+        let shader = self.create_shader(desc.shader);
+
+        T::setup(
+            self.create_pipeline(
+                pip_layout,
+                vertex_layouts,
+                blending,
+                &shader,
+                format,
+                MultisampleState {
+                    count: self.sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+            ),
+            self,
+        )
+    }
+
+    /// Builds `T` with `create_picking_pipeline`, so its shader can write
+    /// object ids for `Renderer::pick`.
+    pub fn picking_pipeline<T>(&self, blending: Blending, format: TextureFormat) -> T
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let desc = T::description();
+        let pip_layout = self.create_pipeline_layout(desc.pipeline_layout);
+        let vertex_layouts = VertexLayout::from_buffers(desc.vertex_layout);
+        let shader = self.create_shader(desc.shader);
+
+        T::setup(
+            self.create_picking_pipeline(pip_layout, vertex_layouts, blending, &shader, format),
+            self,
+        )
+    }
+
+    /// Creates an `IdBuffer` for picking with `Renderer::pick`.
+    pub fn id_buffer(&self, size: Size<u32>) -> IdBuffer {
+        IdBuffer {
+            texture: self.create_texture(
+                size,
+                IdBuffer::FORMAT,
+                TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+                1,
+            ),
+        }
+    }
+
+    /// Builds a pipeline from a description created at runtime, such as one
+    /// with a user-provided shader.
+    ///
+    /// Shader and pipeline validation errors are returned rather than
+    /// panicking. wgpu reports them asynchronously, which is why this is an
+    /// `async fn`; on native backends it is ready immediately.
+    pub async fn dynamic_pipeline<U>(
+        &self,
+        desc: DynamicPipelineDescription,
+        blending: Blending,
+        format: TextureFormat,
+    ) -> Result<DynamicPipeline<U>, PipelineError>
+    where
+        U: bytemuck::Pod,
+    {
+        let uniforms = desc.pipeline_layout.first().ok_or(PipelineError::NoSets)?;
+        pipeline::check_uniform_set(
+            &uniforms
+                .iter()
+                .map(|binding| binding.binding)
+                .collect::<Vec<_>>(),
+        )?;
+
+        self.wgpu.push_error_scope(wgpu::ErrorFilter::Validation);
+        let sets = desc
+            .pipeline_layout
+            .iter()
+            .map(|bindings| Set(bindings))
+            .collect::<Vec<_>>();
+        let pip_layout = self.create_pipeline_layout(&sets);
+        let shader = self.create_shader(&desc.shader);
+        let pipeline = self.create_pipeline(
+            pip_layout,
+            desc.vertex_layouts,
+            blending,
+            &shader,
+            format,
+            MultisampleState {
+                count: self.sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        );
+        if let Some(error) = self.wgpu.pop_error_scope().await {
+            return Err(PipelineError::Validation(error.to_string()));
+        }
+
+        DynamicPipeline::new(pipeline, self)
+    }
+
+    /// Builds a pipeline like `dynamic_pipeline`, blocking the current thread
+    /// until validation has finished.
+    #[cfg(feature = "blocking")]
+    pub fn dynamic_pipeline_blocking<U>(
+        &self,
+        desc: DynamicPipelineDescription,
+        blending: Blending,
+        format: TextureFormat,
+    ) -> Result<DynamicPipeline<U>, PipelineError>
+    where
+        U: bytemuck::Pod,
+    {
+        pollster::block_on(self.dynamic_pipeline(desc, blending, format))
+    }
+
+    /// Builds `T` once for each of `blendings`, sharing a single set of
+    /// layouts, uniforms, and bindings. Panics if `blendings` is empty.
+    pub fn pipeline_variants<T>(
+        &self,
+        blendings: &[Blending],
+        format: TextureFormat,
+    ) -> PipelineVariants<T>
+    where
+        T: AbstractPipeline<'static>,
+    {
+        let (first, rest) = blendings
+            .split_first()
+            .expect("pipeline_variants requires at least one blending");
+        let desc = T::description();
+        let pip_layout = self.create_pipeline_layout(desc.pipeline_layout);
+        let vertex_layouts = VertexLayout::from_buffers(desc.vertex_layout);
+        let shader = self.create_shader(desc.shader);
+        let multisample = MultisampleState {
+            count: self.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        };
+
+        let pipeline = T::setup(
+            self.create_pipeline(
+                pip_layout,
+                vertex_layouts,
+                first.clone(),
+                &shader,
+                format,
+                multisample,
+            ),
+            self,
+        );
+        let others = rest
+            .iter()
+            .map(|blending| {
+                let wgpu = self.create_render_pipeline(
+                    &pipeline.pipeline.layout,
+                    &pipeline.pipeline.vertex_layouts,
+                    blending.clone(),
+                    &shader,
+                    format,
+                    multisample,
+                );
+                (blending.clone(), wgpu)
+            })
+            .collect();
+
+        PipelineVariants::new(pipeline, first.clone(), others)
+    }
+}
+
+impl AsRef<wgpu::Device> for GpuContext {
+    fn as_ref(&self) -> &wgpu::Device {
+        &self.wgpu
+    }
+}
+
+impl AsRef<wgpu::Queue> for GpuContext {
+    fn as_ref(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+}
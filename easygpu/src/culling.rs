@@ -0,0 +1,189 @@
+use crate::binding::BindingGroup;
+use crate::renderer::Draw;
+
+/// An axis-aligned bounding box in world space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl BoundingBox {
+    pub const fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// A flat box spanning two corners of a rectangle at depth `z`.
+    pub fn flat(a: [f32; 2], b: [f32; 2], z: f32) -> Self {
+        Self {
+            min: [a[0].min(b[0]), a[1].min(b[1]), z],
+            max: [a[0].max(b[0]), a[1].max(b[1]), z],
+        }
+    }
+
+    /// The smallest box containing every point, or `None` if there are no
+    /// points.
+    pub fn from_points<I: IntoIterator<Item = [f32; 3]>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |mut bounds, point| {
+            for (axis, value) in point.into_iter().enumerate() {
+                bounds.min[axis] = bounds.min[axis].min(value);
+                bounds.max[axis] = bounds.max[axis].max(value);
+            }
+            bounds
+        }))
+    }
+
+    /// Returns true if the boxes overlap. Touching edges count as overlapping.
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+}
+
+/// The region of world space visible through an orthographic projection.
+///
+/// Culling is conservative: anything reported invisible is guaranteed to be
+/// outside of the view, but some content reported visible may still be clipped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub bounds: BoundingBox,
+}
+
+impl Camera {
+    /// The view of `ScreenTransformation::ortho` called with the same
+    /// arguments.
+    pub fn ortho(left: f32, top: f32, right: f32, bottom: f32, near: f32, far: f32) -> Self {
+        Self {
+            bounds: BoundingBox::new(
+                [left.min(right), top.min(bottom), (-far).min(-near)],
+                [left.max(right), top.max(bottom), (-far).max(-near)],
+            ),
+        }
+    }
+
+    /// A 2D view that does not cull by depth.
+    pub fn flat(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        let mut bounds = BoundingBox::flat([left, top], [right, bottom], 0.);
+        bounds.min[2] = f32::NEG_INFINITY;
+        bounds.max[2] = f32::INFINITY;
+        Self { bounds }
+    }
+
+    pub fn is_visible(&self, bounds: &BoundingBox) -> bool {
+        self.bounds.intersects(bounds)
+    }
+}
+
+/// A list of drawables and their bounds, which can be drawn while skipping
+/// anything outside of a `Camera`'s view.
+#[derive(Debug)]
+pub struct DrawList<T> {
+    entries: Vec<(BoundingBox, T)>,
+}
+
+impl<T> Default for DrawList<T> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T> DrawList<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bounds: BoundingBox, drawable: T) {
+        self.entries.push((bounds, drawable));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(BoundingBox, T)> {
+        self.entries.iter()
+    }
+
+    /// Returns the drawables visible to `camera`, in insertion order.
+    pub fn visible<'a>(&'a self, camera: &Camera) -> impl Iterator<Item = &'a T> + 'a {
+        let camera = *camera;
+        self.entries
+            .iter()
+            .filter(move |(bounds, _)| camera.is_visible(bounds))
+            .map(|(_, drawable)| drawable)
+    }
+}
+
+impl<T: Draw> DrawList<T> {
+    /// Draws every drawable visible to `camera`.
+    pub fn draw_visible<'a>(
+        &'a self,
+        camera: &Camera,
+        binding: &'a BindingGroup,
+        pass: &mut wgpu::RenderPass<'a>,
+    ) {
+        for drawable in self.visible(camera) {
+            drawable.draw(binding, pass);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport() -> Camera {
+        Camera::flat(0., 0., 100., 50.)
+    }
+
+    #[test]
+    fn inside_is_visible() {
+        let bounds = BoundingBox::flat([10., 10.], [20., 20.], 0.);
+        assert!(viewport().is_visible(&bounds));
+    }
+
+    #[test]
+    fn straddling_is_visible() {
+        let camera = viewport();
+        assert!(camera.is_visible(&BoundingBox::flat([-10., 10.], [10., 20.], 0.)));
+        assert!(camera.is_visible(&BoundingBox::flat([90., 40.], [110., 60.], 0.)));
+        // Touching an edge counts as overlapping.
+        assert!(camera.is_visible(&BoundingBox::flat([100., 0.], [120., 10.], 0.)));
+    }
+
+    #[test]
+    fn outside_is_culled() {
+        let camera = viewport();
+        assert!(!camera.is_visible(&BoundingBox::flat([-20., 10.], [-1., 20.], 0.)));
+        assert!(!camera.is_visible(&BoundingBox::flat([10., 51.], [20., 60.], 0.)));
+        assert!(!camera.is_visible(&BoundingBox::flat([101., 51.], [120., 60.], 0.)));
+    }
+
+    #[test]
+    fn ortho_culls_by_depth() {
+        let camera = Camera::ortho(0., 0., 100., 50., 0.1, 10.);
+        assert!(camera.is_visible(&BoundingBox::new([10., 10., -5.], [20., 20., -5.])));
+        assert!(!camera.is_visible(&BoundingBox::new([10., 10., -20.], [20., 20., -11.])));
+    }
+
+    #[test]
+    fn draw_list_keeps_visible_in_order() {
+        let mut list = DrawList::new();
+        list.push(BoundingBox::flat([10., 10.], [20., 20.], 0.), 'a');
+        list.push(BoundingBox::flat([200., 10.], [220., 20.], 0.), 'b');
+        list.push(BoundingBox::flat([-10., -10.], [5., 5.], 0.), 'c');
+        let visible = list.visible(&viewport()).copied().collect::<Vec<_>>();
+        assert_eq!(visible, ['a', 'c']);
+    }
+}
@@ -1,21 +1,22 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
 use figures::Size;
-use wgpu::util::DeviceExt;
-use wgpu::{
-    CompositeAlphaMode, FilterMode, MultisampleState, SubmissionIndex, TextureFormat, TextureUsages,
-};
-
-use crate::binding::{Bind, Binding, BindingGroup, BindingGroupLayout};
-use crate::blending::Blending;
-use crate::buffers::{DepthBuffer, Framebuffer, IndexBuffer, UniformBuffer, VertexBuffer};
-use crate::pipeline::{Pipeline, PipelineLayout, Set};
-use crate::sampler::Sampler;
-use crate::shader::Shader;
-use crate::texture::Texture;
-use crate::vertex::VertexLayout;
+use wgpu::{CompositeAlphaMode, TextureFormat, TextureUsages};
+
+use crate::buffers::{DepthBuffer, DepthMode};
+use crate::color::ColorSpace;
+use crate::context::GpuContext;
+use crate::error::SurfaceConfigError;
 
 pub struct DeviceBuilder<'a> {
     adapter: wgpu::Adapter,
     surface: Option<wgpu::Surface<'a>>,
+    depth_format: TextureFormat,
+    depth_mode: DepthMode,
+    features: wgpu::Features,
+    sample_count: u32,
+    color_space: ColorSpace,
 }
 
 impl<'a> DeviceBuilder<'a> {
@@ -23,6 +24,11 @@ impl<'a> DeviceBuilder<'a> {
         Self {
             adapter,
             surface: None,
+            depth_format: DepthBuffer::FORMAT,
+            depth_mode: DepthMode::default(),
+            features: wgpu::Features::empty(),
+            sample_count: 1,
+            color_space: ColorSpace::default(),
         }
     }
 
@@ -31,12 +37,52 @@ impl<'a> DeviceBuilder<'a> {
         self
     }
 
+    /// Sets the format used for every depth buffer and pipeline created by
+    /// the device. Defaults to `DepthBuffer::FORMAT`.
+    pub fn with_depth_format(mut self, depth_format: TextureFormat) -> DeviceBuilder<'a> {
+        assert!(
+            depth_format.is_depth_stencil_format(),
+            "{depth_format:?} is not a depth format"
+        );
+        self.depth_format = depth_format;
+        self
+    }
+
+    /// Sets how pipelines created by the device compare depth values.
+    pub fn with_depth_mode(mut self, depth_mode: DepthMode) -> DeviceBuilder<'a> {
+        self.depth_mode = depth_mode;
+        self
+    }
+
+    /// Sets the optional wgpu features to request, such as
+    /// `PIPELINE_STATISTICS_QUERY`. Building fails if the adapter doesn't
+    /// support them.
+    pub fn with_features(mut self, features: wgpu::Features) -> DeviceBuilder<'a> {
+        self.features = features;
+        self
+    }
+
+    /// Sets the sample count of textures, depth buffers, and pipelines
+    /// created with `GpuContext`'s MSAA-aware methods, such as
+    /// `GpuContext::framebuffer`. Defaults to 1.
+    pub fn with_sample_count(mut self, sample_count: u32) -> DeviceBuilder<'a> {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Sets the color space of colors given to the device. Defaults to
+    /// `ColorSpace::Srgb`.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> DeviceBuilder<'a> {
+        self.color_space = color_space;
+        self
+    }
+
     pub async fn build(self) -> Result<Device<'a>, wgpu::RequestDeviceError> {
         let (device, queue) = self
             .adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
+                    required_features: self.features,
                     required_limits: wgpu::Limits::default(),
                     label: None,
                     memory_hints: Default::default(),
@@ -46,39 +92,119 @@ impl<'a> DeviceBuilder<'a> {
             .await?;
 
         Ok(Device {
-            wgpu: device,
-            queue,
+            context: Arc::new(GpuContext::new(
+                device,
+                queue,
+                self.depth_format,
+                self.depth_mode,
+                self.sample_count,
+            )),
             surface: self.surface,
-            size: Size::default(), 
+            adapter: self.adapter,
+            size: Size::default(),
+            alpha_mode: CompositeAlphaMode::Auto,
+            surface_format: None,
+            surface_usage: TextureUsages::empty(),
+            color_space: self.color_space,
         })
     }
+
+    /// Builds the device, blocking the current thread until it's ready.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<Device<'a>, wgpu::RequestDeviceError> {
+        pollster::block_on(self.build())
+    }
+}
+
+/// Options for configuring a `Device`'s surface.
+#[derive(Debug, Clone)]
+pub struct SurfaceOptions {
+    /// The requested alpha compositing mode. If the surface does not support
+    /// it, the closest supported mode is used instead. `PreMultiplied` and
+    /// `PostMultiplied` fall back to each other before falling back to `Auto`.
+    pub alpha_mode: CompositeAlphaMode,
+    /// Additional formats that views of the surface's textures may use. Only
+    /// the sRGB or non-sRGB variant of the surface's format is allowed.
+    pub view_formats: Vec<TextureFormat>,
+    /// The usages of the surface's textures. `RENDER_ATTACHMENT` is always
+    /// included. Add `COPY_SRC` to copy presented frames, for screenshots or
+    /// feedback effects, checking `Device::supported_surface_usages` first.
+    pub usage: TextureUsages,
+    /// How many frames the presentation engine may queue ahead of the
+    /// display. Lower values reduce input latency, while higher values
+    /// smooth over uneven frame times. Backends clamp this to the range they
+    /// support. Defaults to 2.
+    pub maximum_frame_latency: u32,
+}
+
+impl Default for SurfaceOptions {
+    fn default() -> Self {
+        Self {
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            maximum_frame_latency: 2,
+        }
+    }
 }
 
+/// A `GpuContext` along with the surface it presents to.
+///
+/// Resources are created through the context, which `Device` dereferences
+/// to.
 #[derive(Debug)]
 pub struct Device<'a> {
-    pub wgpu: wgpu::Device,
-    pub queue: wgpu::Queue,
+    context: Arc<GpuContext>,
     pub surface: Option<wgpu::Surface<'a>>,
+    adapter: wgpu::Adapter,
     size: Size<u32>,
+    alpha_mode: CompositeAlphaMode,
+    surface_format: Option<TextureFormat>,
+    surface_usage: TextureUsages,
+    color_space: ColorSpace,
 }
 
-
 impl Device<'_> {
-    pub const fn device(&self) -> &wgpu::Device {
-        &self.wgpu
+    /// The device's shared context. Clone it to create resources from other
+    /// threads.
+    pub const fn context(&self) -> &Arc<GpuContext> {
+        &self.context
     }
 
     pub const fn size(&self) -> Size<u32> {
         self.size
     }
 
-    pub fn device_mut(&mut self) -> &mut wgpu::Device {
-        &mut self.wgpu
+    /// The color space of clear colors and color texels given to this device.
+    pub const fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    /// The alpha mode the surface was last configured with.
+    pub const fn alpha_mode(&self) -> CompositeAlphaMode {
+        self.alpha_mode
+    }
+
+    pub const fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+
+    /// The capabilities of the surface, or `None` for offscreen devices.
+    pub fn surface_capabilities(&self) -> Option<wgpu::SurfaceCapabilities> {
+        self.surface
+            .as_ref()
+            .map(|surface| surface.get_capabilities(&self.adapter))
     }
 
-    pub fn create_command_encoder(&self) -> wgpu::CommandEncoder {
-        self.wgpu
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None })
+    /// The texture usages the surface supports, or `None` for offscreen
+    /// devices. `RENDER_ATTACHMENT` is always supported.
+    pub fn supported_surface_usages(&self) -> Option<TextureUsages> {
+        self.surface_capabilities()
+            .map(|capabilities| capabilities.usages)
     }
 
     pub fn configure<PM: Into<wgpu::PresentMode>>(
@@ -87,15 +213,52 @@ impl Device<'_> {
         mode: PM,
         format: TextureFormat,
     ) {
+        self.configure_with_options(size, mode, format, &SurfaceOptions::default())
+            .expect("surfaces always support RENDER_ATTACHMENT");
+    }
+
+    /// Configures the surface, failing if it doesn't support
+    /// `options.usage`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                ?format,
+            )
+        )
+    )]
+    pub fn configure_with_options<PM: Into<wgpu::PresentMode>>(
+        &mut self,
+        size: Size<u32>,
+        mode: PM,
+        format: TextureFormat,
+        options: &SurfaceOptions,
+    ) -> Result<(), SurfaceConfigError> {
+        let usage = options.usage | TextureUsages::RENDER_ATTACHMENT;
+        let supported = self
+            .supported_surface_usages()
+            .expect("create_swap_chain only works when initalized with a wgpu::Surface");
+        if !supported.contains(usage) {
+            return Err(SurfaceConfigError::UnsupportedUsage {
+                unsupported: usage.difference(supported),
+                supported,
+            });
+        }
+
+        let alpha_mode = self.supported_alpha_mode(options.alpha_mode);
         let desc = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage,
             format,
             present_mode: mode.into(),
             width: size.width,
             height: size.height,
-            alpha_mode: CompositeAlphaMode::Auto,
-            view_formats: Vec::new(),
-            desired_maximum_frame_latency: 2,
+            alpha_mode,
+            view_formats: options.view_formats.clone(),
+            desired_maximum_frame_latency: options.maximum_frame_latency,
         };
 
         self.surface
@@ -103,352 +266,65 @@ impl Device<'_> {
             .expect("create_swap_chain only works when initalized with a wgpu::Surface")
             .configure(&self.wgpu, &desc);
         self.size = size;
+        self.alpha_mode = alpha_mode;
+        self.surface_format = Some(format);
+        self.surface_usage = usage;
+        Ok(())
     }
 
-    pub fn create_pipeline_layout(&self, ss: &[Set]) -> PipelineLayout {
-        let mut sets = Vec::new();
-        for (i, s) in ss.iter().enumerate() {
-            sets.push(self.create_binding_group_layout(i as u32, s.0));
-        }
-        PipelineLayout { sets }
+    /// The format the surface was last configured with, or `None` if it
+    /// hasn't been configured.
+    pub const fn surface_format(&self) -> Option<TextureFormat> {
+        self.surface_format
     }
 
-    pub fn create_shader(&self, source: &str) -> Shader {
-        Shader {
-            wgpu: self
-                .wgpu
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    source: wgpu::ShaderSource::Wgsl(source.into()),
-                    label: None, // TODO labels would be nice
-                }),
-        }
+    /// The usages the surface was last configured with, or empty if it
+    /// hasn't been configured.
+    pub const fn surface_usage(&self) -> TextureUsages {
+        self.surface_usage
     }
 
-    pub fn create_texture(
-        &self,
-        size: Size<u32>,
-        format: TextureFormat,
-        usage: TextureUsages,
-        sample_count: u32,
-    ) -> Texture {
-        let texture_extent = wgpu::Extent3d {
-            width: size.width,
-            height: size.height,
-            depth_or_array_layers: 1,
+    fn supported_alpha_mode(&self, requested: CompositeAlphaMode) -> CompositeAlphaMode {
+        let Some(capabilities) = self.surface_capabilities() else {
+            return requested;
         };
-        let texture = self.wgpu.create_texture(&wgpu::TextureDescriptor {
-            size: texture_extent,
-            mip_level_count: 1,
-            sample_count,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage,
-            label: None,
-            view_formats: &[],
-        });
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        Texture {
-            wgpu: texture,
-            view: texture_view,
-            extent: texture_extent,
-            format,
-            size,
-        }
-    }
-
-    pub fn create_framebuffer(
-        &self,
-        size: Size<u32>,
-        format: TextureFormat,
-        sample_count: u32,
-    ) -> Framebuffer {
-        let extent = wgpu::Extent3d {
-            width: size.width,
-            height: size.height,
-            depth_or_array_layers: 1,
+        let candidates: &[CompositeAlphaMode] = match requested {
+            CompositeAlphaMode::PreMultiplied => &[
+                CompositeAlphaMode::PreMultiplied,
+                CompositeAlphaMode::PostMultiplied,
+            ],
+            CompositeAlphaMode::PostMultiplied => &[
+                CompositeAlphaMode::PostMultiplied,
+                CompositeAlphaMode::PreMultiplied,
+            ],
+            _ => &[requested],
         };
-        let texture = self.wgpu.create_texture(&wgpu::TextureDescriptor {
-            size: extent,
-            mip_level_count: 1,
-            sample_count,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage: TextureUsages::TEXTURE_BINDING
-                | TextureUsages::COPY_DST
-                | TextureUsages::COPY_SRC
-                | TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-
-        Framebuffer {
-            texture: Texture {
-                wgpu: texture,
-                view,
-                extent,
-                format,
-                size,
-            },
-            depth: self.create_zbuffer(size, sample_count),
-        }
-    }
-
-    pub fn create_zbuffer(&self, size: Size<u32>, sample_count: u32) -> DepthBuffer {
-        let format = DepthBuffer::FORMAT;
-        let extent = wgpu::Extent3d {
-            width: size.width,
-            height: size.height,
-            depth_or_array_layers: 1,
-        };
-        let wgpu = self.wgpu.create_texture(&wgpu::TextureDescriptor {
-            size: extent,
-            label: None,
-            mip_level_count: 1,
-            sample_count,
-            dimension: wgpu::TextureDimension::D2,
-            format,
-            usage: TextureUsages::COPY_DST | TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        });
-        let view = wgpu.create_view(&wgpu::TextureViewDescriptor::default());
-
-        DepthBuffer {
-            texture: Texture {
-                wgpu,
-                view,
-                extent,
-                format,
-                size,
-            },
-        }
-    }
-
-    pub fn create_binding_group(
-        &self,
-        layout: &BindingGroupLayout,
-        binds: &[&dyn Bind],
-    ) -> BindingGroup {
-        assert_eq!(
-            binds.len(),
-            layout.size,
-            "layout slot count does not match bindings"
-        );
-
-        let mut bindings = Vec::new();
-
-        for (i, b) in binds.iter().enumerate() {
-            bindings.push(b.binding(i as u32));
-        }
-
-        BindingGroup::new(
-            layout.set_index,
-            self.wgpu.create_bind_group(&wgpu::BindGroupDescriptor {
-                layout: &layout.wgpu,
-                label: None,
-                entries: bindings.as_slice(),
-            }),
-        )
-    }
-
-    pub fn create_buffer<T: bytemuck::Pod>(&self, vertices: &[T]) -> VertexBuffer
-    where
-        T: 'static + Copy,
-    {
-        VertexBuffer {
-            wgpu: self.create_buffer_from_slice(vertices, wgpu::BufferUsages::VERTEX),
-            size: (vertices.len() * std::mem::size_of::<T>()) as u32,
-        }
-    }
-
-    pub fn create_uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
-    where
-        T: bytemuck::Pod + 'static + Copy,
-    {
-        UniformBuffer {
-            size: std::mem::size_of::<T>(),
-            count: buf.len(),
-            wgpu: self
-                .wgpu
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Uniform Buffer"),
-                    contents: bytemuck::cast_slice(buf),
-                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                }),
-        }
-    }
-
-    pub fn create_index(&self, indices: &[u16]) -> IndexBuffer {
-        let index_buf = self.create_buffer_from_slice(indices, wgpu::BufferUsages::INDEX);
-        IndexBuffer {
-            wgpu: index_buf,
-            elements: indices.len() as u32,
-        }
-    }
-
-    pub fn create_sampler(&self, min_filter: FilterMode, mag_filter: FilterMode) -> Sampler {
-        Sampler {
-            wgpu: self.wgpu.create_sampler(&wgpu::SamplerDescriptor {
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter,
-                min_filter,
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                lod_min_clamp: 0.,
-                lod_max_clamp: 100.0,
-                compare: None,
-                anisotropy_clamp: 1,
-                label: None,
-                border_color: None,
-            }),
-        }
-    }
-
-    pub fn create_binding_group_layout(&self, index: u32, slots: &[Binding]) -> BindingGroupLayout {
-        let mut bindings = Vec::new();
-
-        for s in slots {
-            bindings.push(wgpu::BindGroupLayoutEntry {
-                binding: bindings.len() as u32,
-                visibility: s.stage,
-                ty: s.binding.into(),
-                count: None,
-            });
-        }
-        let layout = self
-            .wgpu
-            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: None,
-                entries: bindings.as_slice(),
-            });
-        BindingGroupLayout::new(index, layout, bindings.len())
-    }
-
-    pub fn create_buffer_from_slice<T: bytemuck::Pod>(
-        &self,
-        slice: &[T],
-        usage: wgpu::BufferUsages,
-    ) -> wgpu::Buffer {
-        self.wgpu
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(slice),
-                usage,
+        candidates
+            .iter()
+            .copied()
+            .find(|mode| {
+                *mode == CompositeAlphaMode::Auto || capabilities.alpha_modes.contains(mode)
             })
+            .unwrap_or(CompositeAlphaMode::Auto)
     }
+}
 
-    pub fn update_uniform_buffer<T: bytemuck::Pod + Copy + 'static>(
-        &self,
-        slice: &[T],
-        buf: &UniformBuffer,
-    ) {
-        self.queue
-            .write_buffer(&buf.wgpu, 0, bytemuck::cast_slice(slice));
-    }
+impl Deref for Device<'_> {
+    type Target = GpuContext;
 
-    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(
-        &mut self,
-        cmds: I,
-    ) -> SubmissionIndex {
-        self.queue.submit(cmds)
+    fn deref(&self) -> &GpuContext {
+        &self.context
     }
+}
 
-    // TODO clippy::too_many_arguments
-    #[allow(clippy::too_many_arguments)]
-    pub fn create_pipeline(
-        &self,
-        pipeline_layout: PipelineLayout,
-        vertex_layout: VertexLayout,
-        blending: Blending,
-        shader: &Shader,
-        swapchain_format: TextureFormat,
-        multisample: MultisampleState,
-    ) -> Pipeline {
-        let vertex_attrs = (&vertex_layout).into();
-
-        let mut sets = Vec::new();
-        for s in pipeline_layout.sets.iter() {
-            sets.push(&s.wgpu);
-        }
-        let layout = &self
-            .wgpu
-            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: None,
-                bind_group_layouts: sets.as_slice(),
-                push_constant_ranges: &[],
-            });
-
-        let (src_factor, dst_factor, operation) = blending.as_wgpu();
-
-        let wgpu = self
-            .wgpu
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: None,
-                layout: Some(layout),
-                vertex: wgpu::VertexState {
-                    module: &shader.wgpu,
-                    entry_point: Some("vs_main"),
-                    buffers: &[vertex_attrs],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None,
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: DepthBuffer::FORMAT,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState {
-                        front: wgpu::StencilFaceState::IGNORE,
-                        back: wgpu::StencilFaceState::IGNORE,
-                        read_mask: 0,
-                        write_mask: 0,
-                    },
-                    bias: wgpu::DepthBiasState {
-                        constant: 0,
-                        slope_scale: 0.,
-                        clamp: 0.,
-                    },
-                }),
-                multisample,
-                multiview: None,
-                fragment: Some(wgpu::FragmentState {
-                    module: &shader.wgpu,
-                    entry_point: Some("fs_main"),
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: swapchain_format,
-                        blend: Some(wgpu::BlendState {
-                            color: wgpu::BlendComponent {
-                                src_factor,
-                                dst_factor,
-                                operation,
-                            },
-                            alpha: wgpu::BlendComponent {
-                                src_factor,
-                                dst_factor,
-                                operation,
-                            },
-                        }),
-
-                        write_mask: wgpu::ColorWrites::ALL,
-                    })],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                cache: None,
-            });
+impl AsRef<wgpu::Device> for Device<'_> {
+    fn as_ref(&self) -> &wgpu::Device {
+        &self.wgpu
+    }
+}
 
-        Pipeline {
-            layout: pipeline_layout,
-            vertex_layout,
-            wgpu,
-        }
+impl AsRef<wgpu::Queue> for Device<'_> {
+    fn as_ref(&self) -> &wgpu::Queue {
+        &self.queue
     }
 }
@@ -8,8 +8,66 @@ pub enum Error {
     RequestDeviceError(#[from] wgpu::RequestDeviceError),
 }
 
+/// An error wrapping a resource created outside of easygpu.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResourceError {
+    #[error("texture usage {actual:?} is missing {required:?}")]
+    MissingTextureUsage {
+        required: wgpu::TextureUsages,
+        actual: wgpu::TextureUsages,
+    },
+    #[error("buffer usage {actual:?} is missing {required:?}")]
+    MissingBufferUsage {
+        required: wgpu::BufferUsages,
+        actual: wgpu::BufferUsages,
+    },
+    #[error("expected a {expected:?} texture, found {actual:?}")]
+    FormatMismatch {
+        expected: wgpu::TextureFormat,
+        actual: wgpu::TextureFormat,
+    },
+    #[error("expected a texture of size {expected:?}, found {actual:?}")]
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    #[error("buffer of {actual} bytes is smaller than {required} bytes")]
+    BufferTooSmall { required: u64, actual: u64 },
+}
+
+/// An error configuring a `Device`'s surface.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SurfaceConfigError {
+    #[error("the surface doesn't support texture usage {unsupported:?}, only {supported:?}")]
+    UnsupportedUsage {
+        unsupported: wgpu::TextureUsages,
+        supported: wgpu::TextureUsages,
+    },
+}
+
+/// A pipeline used with a render target of another format, returned by
+/// `Frame::pass_for` instead of failing wgpu's validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the pipeline draws to {pipeline:?} targets, but the pass's target is {target:?}")]
+pub struct TargetFormatError {
+    pub pipeline: wgpu::TextureFormat,
+    pub target: wgpu::TextureFormat,
+}
+
+/// An error creating a `DynamicPipeline`, returned instead of panicking since
+/// its layout and shader are only known at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PipelineError {
+    #[error("the pipeline layout has no sets")]
+    NoSets,
+    #[error("set 0 must hold exactly one uniform buffer, at binding 0")]
+    InvalidUniformSet,
+    #[error("invalid pipeline: {0}")]
+    Validation(String),
+}
+
 impl From<Error> for io::Error {
     fn from(err: Error) -> io::Error {
-        io::Error::new(io::ErrorKind::Other, err)
+        io::Error::other(err)
     }
 }
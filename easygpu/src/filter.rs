@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{ShaderStages, StorageTextureAccess, TextureFormat, TextureUsages};
+
+use crate::binding::{Binding, BindingGroup, BindingGroupBuilder, BindingType};
+use crate::buffers::UniformBuffer;
+use crate::context::GpuContext;
+use crate::pipeline::{ComputePipeline, Set};
+use crate::texture::Texture;
+
+/// An image filter applied by `Renderer::filter`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    /// Blurs with a normal distribution whose standard deviation is `sigma`
+    /// texels, as one horizontal and one vertical pass.
+    GaussianBlur { sigma: f32 },
+    /// Averages the texels up to `radius` texels away, as one horizontal and
+    /// one vertical pass.
+    BoxBlur { radius: u32 },
+    /// Replaces colors with the strength of the edges through each texel,
+    /// from 0 to 1, using the Sobel operator on luminance. Alpha is kept.
+    Sobel,
+    /// Replaces colors with white where their luminance is at least
+    /// `threshold`, and black elsewhere. Alpha is kept.
+    Threshold { threshold: f32 },
+}
+
+/// The uniforms of `shaders/filter.wgsl`.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct FilterParams {
+    mode: u32,
+    radius: i32,
+    sigma: f32,
+    threshold: f32,
+    direction: [i32; 2],
+    _padding: [u32; 2],
+}
+
+impl FilterParams {
+    const fn blur(radius: i32, sigma: f32, direction: [i32; 2]) -> Self {
+        Self {
+            mode: 0,
+            radius,
+            sigma,
+            threshold: 0.,
+            direction,
+            _padding: [0; 2],
+        }
+    }
+
+    const fn single(mode: u32, threshold: f32) -> Self {
+        Self {
+            mode,
+            radius: 0,
+            sigma: 0.,
+            threshold,
+            direction: [0; 2],
+            _padding: [0; 2],
+        }
+    }
+}
+
+/// The most bind groups `FilterBindings` keeps before starting over.
+const MAX_CACHED_BINDINGS: usize = 32;
+
+/// The filter pipelines a `Renderer` has created, one per destination format.
+#[derive(Debug, Default)]
+pub(crate) struct FilterPipelines {
+    pipelines: HashMap<TextureFormat, ComputePipeline>,
+    cache: FilterCache,
+}
+
+/// Resources reused between filters.
+#[derive(Debug, Default)]
+struct FilterCache {
+    /// The intermediate texture of blurs of each format, replaced when a blur
+    /// of another size is recorded. Blurs recorded onto the same encoder run
+    /// in order, so they can share one.
+    scratch: HashMap<TextureFormat, Texture>,
+    bindings: FilterBindings,
+}
+
+/// The uniforms and bind groups of dispatches, by the textures and parameters
+/// they bind. Uniforms are never rewritten, so dispatches that haven't been
+/// submitted yet keep their parameters.
+#[derive(Debug, Default)]
+struct FilterBindings(HashMap<BindingKey, (UniformBuffer, BindingGroup)>);
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct BindingKey {
+    src: wgpu::Texture,
+    dst: wgpu::Texture,
+    params: [u32; 8],
+}
+
+impl FilterPipelines {
+    /// Records the passes of `kind` from `src` into `dst` onto `encoder`.
+    pub fn record(
+        &mut self,
+        device: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        kind: FilterKind,
+        src: &Texture,
+        dst: &Texture,
+    ) {
+        assert_eq!(
+            src.size, dst.size,
+            "filter source and destination must be the same size"
+        );
+        assert!(
+            src.wgpu != dst.wgpu,
+            "filter source and destination must be different textures"
+        );
+        assert!(
+            src.wgpu.usage().contains(TextureUsages::TEXTURE_BINDING),
+            "filter sources need TextureUsages::TEXTURE_BINDING"
+        );
+        assert!(
+            dst.wgpu.usage().contains(TextureUsages::STORAGE_BINDING),
+            "filter destinations need TextureUsages::STORAGE_BINDING"
+        );
+        let pipeline = self
+            .pipelines
+            .entry(dst.format)
+            .or_insert_with(|| create_pipeline(device, dst.format));
+        let FilterCache { scratch, bindings } = &mut self.cache;
+
+        match kind {
+            FilterKind::GaussianBlur { sigma } => {
+                let horizontal = scratch_texture(scratch, device, dst);
+                let textures = [src, horizontal, dst];
+                let radius = gaussian_radius(sigma);
+                bindings.blur(device, encoder, pipeline, textures, radius, sigma.max(0.));
+            }
+            FilterKind::BoxBlur { radius } => {
+                let horizontal = scratch_texture(scratch, device, dst);
+                let textures = [src, horizontal, dst];
+                bindings.blur(device, encoder, pipeline, textures, radius as i32, 0.);
+            }
+            FilterKind::Sobel => {
+                bindings.dispatch(
+                    device,
+                    encoder,
+                    pipeline,
+                    src,
+                    dst,
+                    FilterParams::single(1, 0.),
+                );
+            }
+            FilterKind::Threshold { threshold } => {
+                bindings.dispatch(
+                    device,
+                    encoder,
+                    pipeline,
+                    src,
+                    dst,
+                    FilterParams::single(2, threshold),
+                );
+            }
+        }
+    }
+}
+
+/// The radius covering three standard deviations of a gaussian blur, past
+/// which texels barely contribute.
+fn gaussian_radius(sigma: f32) -> i32 {
+    (sigma * 3.).ceil().max(0.) as i32
+}
+
+fn create_pipeline(device: &GpuContext, format: TextureFormat) -> ComputePipeline {
+    let name = storage_format_name(format).unwrap_or_else(|| {
+        panic!("{format:?} textures can't be filter destinations, use Rgba8Unorm or Rgba16Float")
+    });
+    let layout = device.create_pipeline_layout(&[Set(&[
+        Binding {
+            binding: BindingType::UniformBuffer,
+            stage: ShaderStages::COMPUTE,
+        },
+        Binding {
+            binding: BindingType::SampledTexture {
+                multisampled: false,
+            },
+            stage: ShaderStages::COMPUTE,
+        },
+        Binding {
+            binding: BindingType::StorageTexture {
+                access: StorageTextureAccess::WriteOnly,
+                format,
+            },
+            stage: ShaderStages::COMPUTE,
+        },
+    ])]);
+    let shader =
+        device.create_shader(&include_str!("shaders/filter.wgsl").replace("STORAGE_FORMAT", name));
+    device.create_compute_pipeline(layout, &shader)
+}
+
+/// The scratch texture for blurring into `dst`, created if there is none of
+/// its size and format yet.
+fn scratch_texture<'a>(
+    scratch: &'a mut HashMap<TextureFormat, Texture>,
+    device: &GpuContext,
+    dst: &Texture,
+) -> &'a Texture {
+    let texture = scratch.entry(dst.format).or_insert_with(|| {
+        device.create_storage_texture(dst.size, dst.format, StorageTextureAccess::WriteOnly)
+    });
+    if texture.size != dst.size {
+        *texture =
+            device.create_storage_texture(dst.size, dst.format, StorageTextureAccess::WriteOnly);
+    }
+    texture
+}
+
+impl FilterBindings {
+    /// Blurs the first of `textures` horizontally into the second, then
+    /// vertically into the third.
+    fn blur(
+        &mut self,
+        device: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &ComputePipeline,
+        [src, horizontal, dst]: [&Texture; 3],
+        radius: i32,
+        sigma: f32,
+    ) {
+        self.dispatch(
+            device,
+            encoder,
+            pipeline,
+            src,
+            horizontal,
+            FilterParams::blur(radius, sigma, [1, 0]),
+        );
+        self.dispatch(
+            device,
+            encoder,
+            pipeline,
+            horizontal,
+            dst,
+            FilterParams::blur(radius, sigma, [0, 1]),
+        );
+    }
+
+    fn dispatch(
+        &mut self,
+        device: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &ComputePipeline,
+        src: &Texture,
+        dst: &Texture,
+        params: FilterParams,
+    ) {
+        let key = BindingKey {
+            src: src.wgpu.clone(),
+            dst: dst.wgpu.clone(),
+            params: bytemuck::cast(params),
+        };
+        if !self.0.contains_key(&key) && self.0.len() >= MAX_CACHED_BINDINGS {
+            // Pending dispatches keep their own bind groups alive.
+            self.0.clear();
+        }
+        let (_, binding) = self.0.entry(key).or_insert_with(|| {
+            let uniforms = device.create_uniform_buffer(&[params]);
+            let binding = BindingGroupBuilder::new(&pipeline.layout.sets[0])
+                .uniforms(&uniforms)
+                .texture(src)
+                .storage_texture(dst)
+                .build(device);
+            (uniforms, binding)
+        });
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+        pass.set_pipeline(&pipeline.wgpu);
+        pass.set_bind_group(binding.set_index, &binding.wgpu, &[]);
+        pass.dispatch_workgroups(dst.size.width.div_ceil(8), dst.size.height.div_ceil(8), 1);
+    }
+}
+
+/// The WGSL name of `format` in `texture_storage_2d`, if it can be written
+/// by default and sampled by the blur's second pass.
+const fn storage_format_name(format: TextureFormat) -> Option<&'static str> {
+    Some(match format {
+        TextureFormat::Rgba8Unorm => "rgba8unorm",
+        TextureFormat::Rgba8Snorm => "rgba8snorm",
+        TextureFormat::Rgba16Float => "rgba16float",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_radius_covers_three_sigma() {
+        assert_eq!(gaussian_radius(1.), 3);
+        assert_eq!(gaussian_radius(0.5), 2);
+        assert_eq!(gaussian_radius(2.1), 7);
+        assert_eq!(gaussian_radius(0.), 0);
+        assert_eq!(gaussian_radius(-1.), 0);
+    }
+
+    #[test]
+    fn params_match_the_shader_layout() {
+        // `Params` in filter.wgsl is 32 bytes: four scalars, a `vec2<i32>`
+        // and padding to its 8-byte alignment.
+        assert_eq!(std::mem::size_of::<FilterParams>(), 32);
+
+        let blur: [u32; 8] = bytemuck::cast(FilterParams::blur(4, 1.5, [0, 1]));
+        assert_eq!(blur, [0, 4, 1.5f32.to_bits(), 0, 0, 1, 0, 0]);
+
+        let threshold: [u32; 8] = bytemuck::cast(FilterParams::single(2, 0.25));
+        assert_eq!(threshold, [2, 0, 0, 0.25f32.to_bits(), 0, 0, 0, 0]);
+
+        let horizontal: [u32; 8] = bytemuck::cast(FilterParams::blur(1, 0., [1, 0]));
+        assert_eq!(horizontal[4..6], [1, 0]);
+    }
+
+    #[test]
+    fn only_writable_formats_are_destinations() {
+        assert_eq!(
+            storage_format_name(TextureFormat::Rgba8Unorm),
+            Some("rgba8unorm")
+        );
+        assert_eq!(
+            storage_format_name(TextureFormat::Rgba16Float),
+            Some("rgba16float")
+        );
+        assert_eq!(storage_format_name(TextureFormat::Rgba8UnormSrgb), None);
+        assert_eq!(storage_format_name(TextureFormat::Bgra8Unorm), None);
+    }
+}
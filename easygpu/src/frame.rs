@@ -1,37 +1,153 @@
 use wgpu::TextureView;
 
-use crate::buffers::UniformBuffer;
-use crate::renderer::{PassOp, RenderPassExt, RenderTarget};
+use crate::buffers::{DepthMode, UniformBuffer};
+use crate::color::ColorSpace;
+use crate::error::TargetFormatError;
+use crate::picking::IdBuffer;
+use crate::pipeline::Pipeline;
+use crate::query::{StatisticsPass, StatisticsQuery};
+use crate::renderer::{begin_pass, DepthAttachment, DepthOp, Pass, PassOp, RenderTarget};
 
 #[derive(Debug)]
 pub struct Frame {
     pub encoder: wgpu::CommandEncoder,
+    /// Determines the value depth buffers are cleared to by `pass`.
+    pub depth_mode: DepthMode,
+    /// The color space clear colors are converted from.
+    pub color_space: ColorSpace,
 }
 
 impl Frame {
     pub fn new(encoder: wgpu::CommandEncoder) -> Self {
-        Self { encoder }
+        Self {
+            encoder,
+            depth_mode: DepthMode::default(),
+            color_space: ColorSpace::default(),
+        }
     }
 
+    /// Begins a render pass that clears the depth buffer.
     pub fn pass<'a>(
         &'a mut self,
-        op: PassOp,
+        op: PassOp<'_>,
         view: &'a impl RenderTarget,
         multisample_buffer: Option<&'a TextureView>,
-    ) -> wgpu::RenderPass<'a> {
+    ) -> Pass<'a> {
+        let depth_op = DepthOp::Clear(self.depth_mode.clear_value());
+        self.pass_with_depth_op(op, depth_op, view, multisample_buffer)
+    }
+
+    /// Begins a render pass like `pass`, first checking that each of
+    /// `pipelines` draws to targets of `view`'s format, instead of panicking
+    /// when one of another format is bound to the pass.
+    pub fn pass_for<'a>(
+        &'a mut self,
+        pipelines: &[&Pipeline],
+        op: PassOp<'_>,
+        view: &'a impl RenderTarget,
+        multisample_buffer: Option<&'a TextureView>,
+    ) -> Result<Pass<'a>, TargetFormatError> {
+        for pipeline in pipelines {
+            pipeline.check_target(view)?;
+        }
+        Ok(self.pass(op, view, multisample_buffer))
+    }
+
+    /// Begins a render pass using `depth_op` for the depth buffer, such as
+    /// `DepthOp::Load` to keep the depth from an earlier pass.
+    pub fn pass_with_depth_op<'a>(
+        &'a mut self,
+        op: PassOp<'_>,
+        depth_op: DepthOp,
+        view: &'a impl RenderTarget,
+        multisample_buffer: Option<&'a TextureView>,
+    ) -> Pass<'a> {
         let (pass_view, resolve_target) = match multisample_buffer {
             Some(buffer) => (buffer, Some(view.color_target())),
             None => (view.color_target(), None),
         };
-        wgpu::RenderPass::begin(
+        let loads = self.color_loads(op, view);
+        begin_pass(
             &mut self.encoder,
-            pass_view,
-            resolve_target,
-            view.zdepth_target(),
-            op,
+            "easygpu pass",
+            view.target_size(),
+            view.color_format(),
+            &[(pass_view, resolve_target)],
+            loads,
+            DepthAttachment {
+                view: view.zdepth_target(),
+                format: view.zdepth_format(),
+                op: depth_op,
+            },
         )
     }
 
+    /// The load operation of `view`'s color target, with clear colors
+    /// converted from the frame's color space.
+    fn color_loads(
+        &self,
+        op: PassOp<'_>,
+        view: &impl RenderTarget,
+    ) -> Vec<wgpu::LoadOp<wgpu::Color>> {
+        match view.color_format() {
+            Some(format) => op.to_wgpu_with(1, |color| self.color_space.to_target(color, format)),
+            None => op.to_wgpu(1),
+        }
+    }
+
+    /// Begins a render pass like `pass` that records pipeline statistics into
+    /// query `index` of `query`.
+    pub fn pass_with_stats<'a>(
+        &'a mut self,
+        op: PassOp<'_>,
+        view: &'a impl RenderTarget,
+        multisample_buffer: Option<&'a TextureView>,
+        query: &'a StatisticsQuery,
+        index: u32,
+    ) -> StatisticsPass<'a> {
+        let mut pass = self.pass(op, view, multisample_buffer);
+        pass.begin_pipeline_statistics_query(&query.wgpu, index);
+        StatisticsPass { pass }
+    }
+
+    /// Begins a render pass that also writes object ids into `ids`, for
+    /// pipelines created with `GpuContext::picking_pipeline`. `op` applies to
+    /// the color target, and `ids` is cleared to `IdBuffer::NONE`, so
+    /// `PassOp::ClearEach` needs exactly one color, for the color target.
+    ///
+    /// The target must be single-sampled and the same size as `ids`.
+    pub fn pass_with_ids<'a>(
+        &'a mut self,
+        op: PassOp<'_>,
+        view: &'a impl RenderTarget,
+        ids: &'a IdBuffer,
+    ) -> Pass<'a> {
+        let mut loads = self.color_loads(op, view);
+        loads.push(wgpu::LoadOp::Clear(wgpu::Color {
+            r: f64::from(IdBuffer::NONE),
+            ..wgpu::Color::TRANSPARENT
+        }));
+        begin_pass(
+            &mut self.encoder,
+            "easygpu id pass",
+            view.target_size(),
+            view.color_format(),
+            &[(view.color_target(), None), (&ids.texture.view, None)],
+            loads,
+            DepthAttachment {
+                view: view.zdepth_target(),
+                format: view.zdepth_format(),
+                op: DepthOp::Clear(self.depth_mode.clear_value()),
+            },
+        )
+    }
+
+    /// Records copying the results of `query` so they can be read with
+    /// `Renderer::read_statistics` once the frame is presented.
+    pub fn resolve_statistics(&mut self, query: &StatisticsQuery) {
+        query.resolve(&mut self.encoder);
+    }
+
     pub fn copy(&mut self, src: &UniformBuffer, dst: &UniformBuffer) {
         self.encoder.copy_buffer_to_buffer(
             &src.wgpu,
@@ -46,7 +162,23 @@ impl Frame {
         &self.encoder
     }
 
+    /// The frame's encoder, as an escape hatch for raw wgpu commands such as
+    /// buffer copies between passes. Everything recorded is submitted with
+    /// the frame.
     pub fn encoder_mut(&mut self) -> &mut wgpu::CommandEncoder {
         &mut self.encoder
     }
+
+    /// Begins a compute pass recorded onto the frame's encoder, ordered
+    /// relative to the frame's other passes.
+    pub fn compute_pass(&mut self) -> wgpu::ComputePass<'_> {
+        self.encoder
+            .begin_compute_pass(&wgpu::ComputePassDescriptor::default())
+    }
+}
+
+impl AsRef<wgpu::CommandEncoder> for Frame {
+    fn as_ref(&self) -> &wgpu::CommandEncoder {
+        &self.encoder
+    }
 }
@@ -1,40 +1,72 @@
 #![warn(clippy::all)]
 
+pub mod animation;
 pub mod binding;
 pub mod blending;
 pub mod buffers;
 pub mod canvas;
 pub mod color;
+pub mod context;
+pub mod culling;
 pub mod device;
 pub mod error;
+pub mod filter;
 pub mod frame;
+pub mod mesh;
+#[cfg(feature = "gltf")]
+pub mod model;
+pub mod picking;
 pub mod pipeline;
+pub mod poll;
+pub mod query;
+pub mod readback;
 pub mod renderable;
 pub mod renderer;
+pub mod retired;
 pub mod sampler;
 pub mod shader;
 pub mod texture;
+pub mod timing;
 pub mod transform;
 pub mod vertex;
 
+#[cfg(feature = "glam")]
+pub use glam;
+#[cfg(feature = "gltf")]
+pub use gltf;
+#[cfg(feature = "mint")]
+pub use mint;
 pub use {figures, wgpu};
 
 pub mod prelude {
+    pub use super::animation::*;
     pub use super::binding::*;
-    pub use super::buffers::*;
     pub use super::blending::*;
+    pub use super::buffers::*;
     pub use super::canvas::*;
     pub use super::color::*;
+    pub use super::context::*;
+    pub use super::culling::*;
     pub use super::device::*;
     pub use super::error::*;
+    pub use super::filter::*;
     pub use super::frame::*;
+    pub use super::mesh::*;
+    #[cfg(feature = "gltf")]
+    pub use super::model::*;
+    pub use super::picking::*;
     pub use super::pipeline::*;
+    pub use super::poll::*;
+    pub use super::query::*;
+    pub use super::readback::*;
     pub use super::renderable::*;
     pub use super::renderer::*;
+    pub use super::retired::*;
     pub use super::sampler::*;
     pub use super::shader::*;
     pub use super::texture::*;
+    pub use super::timing::*;
     pub use super::transform::*;
-    pub use super::vertex::*;
+    pub use super::vertex::{VertexBufferDesc, VertexFormat, VertexLayout};
     pub use super::wgpu;
 }
@@ -0,0 +1,43 @@
+use std::ops::Range;
+
+use crate::binding::BindingGroup;
+use crate::buffers::{IndexBuffer, IndexElement, VertexBuffer};
+use crate::context::GpuContext;
+use crate::renderer::{Draw, RenderPassExt};
+
+/// A vertex buffer paired with an index buffer describing its triangles.
+#[derive(Debug)]
+pub struct Mesh {
+    pub vertices: VertexBuffer,
+    pub indices: IndexBuffer,
+    /// The range of elements in `indices` to draw.
+    pub range: Range<u32>,
+}
+
+impl Mesh {
+    /// Creates a mesh that draws every element of `indices`.
+    pub fn new(vertices: VertexBuffer, indices: IndexBuffer) -> Self {
+        let range = 0..indices.elements;
+        Self {
+            vertices,
+            indices,
+            range,
+        }
+    }
+
+    /// Uploads `vertices` and `indices` into new buffers.
+    pub fn from_slices<V, I>(device: &GpuContext, vertices: &[V], indices: &[I]) -> Self
+    where
+        V: bytemuck::Pod + 'static + Copy,
+        I: IndexElement,
+    {
+        Self::new(device.create_buffer(vertices), device.create_index(indices))
+    }
+}
+
+impl Draw for Mesh {
+    fn draw<'a>(&'a self, binding: &'a BindingGroup, pass: &mut wgpu::RenderPass<'a>) {
+        pass.set_binding(binding, &[]);
+        pass.draw_mesh(self);
+    }
+}
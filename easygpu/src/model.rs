@@ -0,0 +1,244 @@
+//! Loading glTF 2.0 meshes into easygpu types.
+//!
+//! Only mesh geometry and base-color materials are imported. Node transforms,
+//! skins, and animations are ignored, so every primitive is loaded in its
+//! mesh's local space.
+
+use std::path::Path;
+
+use bytemuck::{Pod, Zeroable};
+use figures::Size;
+use gltf::image::Format;
+use gltf::mesh::Mode;
+
+use crate::color::Rgba;
+use crate::context::GpuContext;
+use crate::mesh::Mesh;
+use crate::texture::Texture;
+use crate::vertex::VertexFormat;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("gltf error: {0}")]
+    Gltf(#[from] gltf::Error),
+    #[error("primitive {primitive} of mesh {mesh} has no positions")]
+    MissingPositions { mesh: usize, primitive: usize },
+    #[error("primitive {primitive} of mesh {mesh} uses unsupported mode {mode:?}")]
+    UnsupportedMode {
+        mesh: usize,
+        primitive: usize,
+        mode: Mode,
+    },
+    #[error("image {image} uses unsupported format {format:?}")]
+    UnsupportedImageFormat { image: usize, format: Format },
+}
+
+/// The vertex format produced by the importer.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Pod, Zeroable)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl ModelVertex {
+    /// The vertex layout matching this type, for use in a
+    /// `PipelineDescription`.
+    pub const FORMATS: &'static [VertexFormat] = &[
+        VertexFormat::Floatx3,
+        VertexFormat::Floatx3,
+        VertexFormat::Floatx2,
+    ];
+}
+
+/// The base-color portion of a glTF PBR material.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+    pub base_color: Rgba,
+    /// Index into `Model::textures`.
+    pub base_color_texture: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct Primitive {
+    pub mesh: Mesh,
+    /// Index into `Model::materials`.
+    pub material: Option<usize>,
+}
+
+/// Every triangle primitive in a glTF document, uploaded to the GPU.
+#[derive(Debug)]
+pub struct Model {
+    pub primitives: Vec<Primitive>,
+    pub materials: Vec<Material>,
+    /// One texture per glTF image, in document order. Images used as base
+    /// color or emissive textures are `Rgba8UnormSrgb`, and the rest, such as
+    /// normal and metallic-roughness maps, hold linear data in `Rgba8Unorm`.
+    pub textures: Vec<Texture>,
+}
+
+impl Model {
+    /// Loads a `.gltf` or `.glb` file along with any external buffers and
+    /// images it references.
+    pub fn load_gltf<P: AsRef<Path>>(context: &GpuContext, path: P) -> Result<Self, ImportError> {
+        let (document, buffers, images) = gltf::import(path)?;
+        Self::from_gltf(context, &document, &buffers, &images)
+    }
+
+    /// Loads a glTF document from memory. External references are not
+    /// resolved.
+    pub fn load_gltf_slice(context: &GpuContext, bytes: &[u8]) -> Result<Self, ImportError> {
+        let (document, buffers, images) = gltf::import_slice(bytes)?;
+        Self::from_gltf(context, &document, &buffers, &images)
+    }
+
+    /// Uploads an already-imported glTF document.
+    pub fn from_gltf(
+        context: &GpuContext,
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+        images: &[gltf::image::Data],
+    ) -> Result<Self, ImportError> {
+        let mut srgb = vec![false; images.len()];
+        for material in document.materials() {
+            let color_textures = [
+                material.pbr_metallic_roughness().base_color_texture(),
+                material.emissive_texture(),
+            ];
+            for info in color_textures.into_iter().flatten() {
+                srgb[info.texture().source().index()] = true;
+            }
+        }
+        let textures = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let format = if srgb[index] {
+                    wgpu::TextureFormat::Rgba8UnormSrgb
+                } else {
+                    wgpu::TextureFormat::Rgba8Unorm
+                };
+                upload_image(context, index, image, format)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let materials = document
+            .materials()
+            .map(|material| {
+                let pbr = material.pbr_metallic_roughness();
+                let [r, g, b, a] = pbr.base_color_factor();
+                Material {
+                    base_color: Rgba::new(r, g, b, a),
+                    base_color_texture: pbr
+                        .base_color_texture()
+                        .map(|info| info.texture().source().index()),
+                }
+            })
+            .collect();
+
+        let mut primitives = Vec::new();
+        for mesh in document.meshes() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != Mode::Triangles {
+                    return Err(ImportError::UnsupportedMode {
+                        mesh: mesh.index(),
+                        primitive: primitive.index(),
+                        mode: primitive.mode(),
+                    });
+                }
+
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let mut vertices = reader
+                    .read_positions()
+                    .ok_or(ImportError::MissingPositions {
+                        mesh: mesh.index(),
+                        primitive: primitive.index(),
+                    })?
+                    .map(|position| ModelVertex {
+                        position,
+                        ..ModelVertex::default()
+                    })
+                    .collect::<Vec<_>>();
+                if let Some(normals) = reader.read_normals() {
+                    for (vertex, normal) in vertices.iter_mut().zip(normals) {
+                        vertex.normal = normal;
+                    }
+                }
+                if let Some(uvs) = reader.read_tex_coords(0) {
+                    for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+                        vertex.uv = uv;
+                    }
+                }
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect::<Vec<_>>(),
+                    None => (0..vertices.len() as u32).collect(),
+                };
+
+                primitives.push(Primitive {
+                    mesh: context.mesh(&vertices, &indices),
+                    material: primitive.material().index(),
+                });
+            }
+        }
+
+        Ok(Self {
+            primitives,
+            materials,
+            textures,
+        })
+    }
+}
+
+fn upload_image(
+    context: &GpuContext,
+    index: usize,
+    image: &gltf::image::Data,
+    format: wgpu::TextureFormat,
+) -> Result<Texture, ImportError> {
+    let channels = match image.format {
+        Format::R8 => 1,
+        Format::R8G8 => 2,
+        Format::R8G8B8 => 3,
+        Format::R8G8B8A8 => 4,
+        format => {
+            return Err(ImportError::UnsupportedImageFormat {
+                image: index,
+                format,
+            })
+        }
+    };
+    let mut texels = Vec::with_capacity(image.width as usize * image.height as usize * 4);
+    for pixel in image.pixels.chunks_exact(channels) {
+        match *pixel {
+            [l] => texels.extend_from_slice(&[l, l, l, 255]),
+            [l, a] => texels.extend_from_slice(&[l, l, l, a]),
+            [r, g, b] => texels.extend_from_slice(&[r, g, b, 255]),
+            _ => texels.extend_from_slice(pixel),
+        }
+    }
+
+    let texture = context.texture(
+        Size::new(image.width, image.height),
+        format,
+        wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        false,
+    );
+    context.queue.write_texture(
+        wgpu::TexelCopyTextureInfo {
+            texture: &texture.wgpu,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &texels,
+        wgpu::TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * image.width),
+            rows_per_image: Some(image.height),
+        },
+        texture.extent,
+    );
+
+    Ok(texture)
+}
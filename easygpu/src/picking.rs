@@ -0,0 +1,35 @@
+//! Hit-testing by rendering object ids.
+//!
+//! Pipelines created with `GpuContext::picking_pipeline` write a `u32` id for
+//! each fragment to a second color target. Passes begun with
+//! `Frame::pass_with_ids` render into an `IdBuffer` alongside the usual color
+//! target, and `Renderer::pick` reads back the id under a pixel.
+
+use figures::Size;
+
+use crate::texture::Texture;
+
+/// An `R32Uint` texture of object ids, created by `GpuContext::id_buffer`.
+///
+/// The buffer is cleared to `IdBuffer::NONE` at the start of each picking
+/// pass, so ids written by shaders should start at 1.
+#[derive(Debug)]
+pub struct IdBuffer {
+    pub texture: Texture,
+}
+
+impl IdBuffer {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+    /// The id of pixels no object was drawn to.
+    pub const NONE: u32 = 0;
+
+    pub const fn size(&self) -> Size<u32> {
+        self.texture.size
+    }
+}
+
+impl AsRef<wgpu::TextureView> for IdBuffer {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.texture.view
+    }
+}
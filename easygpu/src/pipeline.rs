@@ -1,10 +1,16 @@
+use std::marker::PhantomData;
 use std::ops::Deref;
 
+use wgpu::TextureFormat;
+
 use crate::{
-    binding::{Binding, BindingGroup, BindingGroupLayout},
+    binding::{Binding, BindingGroup, BindingGroupBuilder, BindingGroupLayout, BindingType},
+    blending::Blending,
     buffers::UniformBuffer,
-    device::Device,
-    vertex::{VertexFormat, VertexLayout},
+    context::GpuContext,
+    error::{PipelineError, TargetFormatError},
+    renderer::RenderTarget,
+    vertex::{VertexBufferDesc, VertexLayout},
 };
 
 #[derive(Debug)]
@@ -12,9 +18,42 @@ pub struct Pipeline {
     pub wgpu: wgpu::RenderPipeline,
 
     pub layout: PipelineLayout,
-    pub vertex_layout: VertexLayout,
+    pub vertex_layouts: Vec<VertexLayout>,
+    /// The format of the color targets the pipeline draws to.
+    pub format: TextureFormat,
+}
+
+impl Pipeline {
+    /// Checks that the pipeline can draw to `target`. Targets that don't
+    /// report their `RenderTarget::color_format` always pass.
+    pub fn check_target(&self, target: &impl RenderTarget) -> Result<(), TargetFormatError> {
+        match target.color_format() {
+            Some(format) => self.check_format(format),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that the pipeline can draw to targets of `format`.
+    pub fn check_format(&self, format: TextureFormat) -> Result<(), TargetFormatError> {
+        if format == self.format {
+            Ok(())
+        } else {
+            Err(TargetFormatError {
+                pipeline: self.format,
+                target: format,
+            })
+        }
+    }
 }
 
+/// A compute shader and the layout of the bindings it uses, created by
+/// `GpuContext::create_compute_pipeline`.
+#[derive(Debug)]
+pub struct ComputePipeline {
+    pub wgpu: wgpu::ComputePipeline,
+
+    pub layout: PipelineLayout,
+}
 
 #[derive(Debug)]
 pub struct Set<'a>(pub &'a [Binding]);
@@ -30,21 +69,210 @@ pub struct PipelineCore {
     pub uniforms: UniformBuffer,
 }
 
-pub trait AbstractPipeline<'a>: Deref<Target = PipelineCore> {
+/// A pipeline whose uniforms are updated by `Renderer::update_pipeline`.
+pub trait PreparePipeline<'a>: Deref<Target = PipelineCore> {
     type PrepareContext;
     type Uniforms: bytemuck::Pod + Copy + 'static;
 
-    fn description() -> PipelineDescription<'a>;
-    fn setup(pip: Pipeline, dev: &Device) -> Self;
     fn prepare(
         &'a self,
         context: Self::PrepareContext,
     ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)>;
 }
 
+pub trait AbstractPipeline<'a>: PreparePipeline<'a> {
+    fn description() -> PipelineDescription<'a>;
+    fn setup(pip: Pipeline, dev: &GpuContext) -> Self;
+}
+
+/// An owned `PipelineDescription`, for pipelines that are only known at
+/// runtime.
+#[derive(Debug)]
+pub struct DynamicPipelineDescription {
+    /// One entry per vertex buffer slot, starting at slot 0. See
+    /// `VertexLayout::from_buffers`.
+    pub vertex_layouts: Vec<VertexLayout>,
+    /// The bindings of each set. Set 0 must contain exactly one uniform
+    /// buffer, which `DynamicPipeline` binds to its uniforms.
+    pub pipeline_layout: Vec<Vec<Binding>>,
+    pub shader: String,
+}
+
+/// A pipeline created from a `DynamicPipelineDescription` rather than an
+/// `AbstractPipeline` implementation. `U` is the type of its uniforms.
+pub struct DynamicPipeline<U> {
+    core: PipelineCore,
+    _uniforms: PhantomData<U>,
+}
+
+impl<U: bytemuck::Pod> DynamicPipeline<U> {
+    /// Binds a zeroed `U` to set 0 of `pipeline`, which must hold exactly one
+    /// uniform buffer.
+    pub fn new(pipeline: Pipeline, dev: &GpuContext) -> Result<Self, PipelineError> {
+        let set = pipeline.layout.sets.first().ok_or(PipelineError::NoSets)?;
+        check_uniform_set(&set.slots)?;
+        let uniforms = dev.create_uniform_buffer(&[U::zeroed()]);
+        let bindings = BindingGroupBuilder::new(set).uniforms(&uniforms).build(dev);
+
+        Ok(Self {
+            core: PipelineCore {
+                pipeline,
+                bindings,
+                uniforms,
+            },
+            _uniforms: PhantomData,
+        })
+    }
+}
+
+/// Checks the slots of a dynamic pipeline's first set, which binds its
+/// uniforms.
+pub(crate) fn check_uniform_set(slots: &[BindingType]) -> Result<(), PipelineError> {
+    match slots {
+        [BindingType::UniformBuffer] => Ok(()),
+        _ => Err(PipelineError::InvalidUniformSet),
+    }
+}
+
+impl<U> Deref for DynamicPipeline<U> {
+    type Target = PipelineCore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.core
+    }
+}
+
+impl<'a, U: bytemuck::Pod> PreparePipeline<'a> for DynamicPipeline<U> {
+    type PrepareContext = U;
+    type Uniforms = U;
+
+    fn prepare(&'a self, uniforms: U) -> Option<(&'a UniformBuffer, Vec<U>)> {
+        Some((&self.core.uniforms, vec![uniforms]))
+    }
+}
+
+/// A pipeline built once per blend state. Every variant shares the layouts,
+/// uniforms, and bindings of `pipeline`.
+pub struct PipelineVariants<T> {
+    pipeline: T,
+    variants: Vec<(Blending, wgpu::RenderPipeline)>,
+}
+
+impl<T> PipelineVariants<T>
+where
+    T: Deref<Target = PipelineCore>,
+{
+    /// `pipeline` is used for the first blending in the list, and `others` for
+    /// the rest.
+    pub fn new(
+        pipeline: T,
+        blending: Blending,
+        others: Vec<(Blending, wgpu::RenderPipeline)>,
+    ) -> Self {
+        let mut variants = Vec::with_capacity(others.len() + 1);
+        variants.push((blending, pipeline.pipeline.wgpu.clone()));
+        variants.extend(others);
+        Self { pipeline, variants }
+    }
+
+    /// The shared pipeline, whose uniforms are used by every variant.
+    pub fn pipeline(&self) -> &T {
+        &self.pipeline
+    }
+
+    /// Returns the variant for `blending`, or `None` if it wasn't requested
+    /// when the variants were created.
+    pub fn variant(&self, blending: Blending) -> Option<PipelineVariant<'_>> {
+        self.variants
+            .iter()
+            .find(|(variant, _)| *variant == blending)
+            .map(|(_, wgpu)| PipelineVariant {
+                wgpu,
+                core: &self.pipeline,
+            })
+    }
+
+    pub fn blendings(&self) -> impl Iterator<Item = &Blending> {
+        self.variants.iter().map(|(blending, _)| blending)
+    }
+}
+
+/// A single blend state of a `PipelineVariants`, which can be bound with
+/// `RenderPassExt::set_pipeline_variant`.
+#[derive(Clone, Copy)]
+pub struct PipelineVariant<'a> {
+    pub wgpu: &'a wgpu::RenderPipeline,
+    pub core: &'a PipelineCore,
+}
+
+/// How a pipeline uses the stencil aspect of the depth buffer. Every mode
+/// but `Ignore` compares against the pass's stencil reference, set with
+/// `wgpu::RenderPass::set_stencil_reference`, and needs a depth format with a
+/// stencil aspect such as `Depth24PlusStencil8`. See
+/// `DeviceBuilder::with_depth_format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StencilMode {
+    /// The stencil is neither tested nor written.
+    #[default]
+    Ignore,
+    /// Fragments are only drawn where the stencil equals the reference.
+    Equal,
+    /// Increments the stencil where it equals the reference, without writing
+    /// color or depth.
+    Increment,
+    /// Decrements the stencil where it equals the reference, without writing
+    /// color or depth.
+    Decrement,
+}
+
+impl StencilMode {
+    pub(crate) fn state(self) -> wgpu::StencilState {
+        let pass_op = match self {
+            StencilMode::Ignore => return wgpu::StencilState::default(),
+            StencilMode::Equal => wgpu::StencilOperation::Keep,
+            StencilMode::Increment => wgpu::StencilOperation::IncrementClamp,
+            StencilMode::Decrement => wgpu::StencilOperation::DecrementClamp,
+        };
+        let face = wgpu::StencilFaceState {
+            compare: wgpu::CompareFunction::Equal,
+            fail_op: wgpu::StencilOperation::Keep,
+            depth_fail_op: wgpu::StencilOperation::Keep,
+            pass_op,
+        };
+        wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: !0,
+            write_mask: if pass_op == wgpu::StencilOperation::Keep {
+                0
+            } else {
+                !0
+            },
+        }
+    }
+
+    /// Returns true if the mode only writes the stencil.
+    pub(crate) const fn is_stencil_only(self) -> bool {
+        matches!(self, StencilMode::Increment | StencilMode::Decrement)
+    }
+}
+
 #[derive(Debug)]
 pub struct PipelineDescription<'a> {
-    pub vertex_layout: &'a [VertexFormat],
+    /// One entry per vertex buffer slot, starting at slot 0.
+    pub vertex_layout: &'a [VertexBufferDesc<'a>],
     pub pipeline_layout: &'a [Set<'a>],
     pub shader: &'static str,
 }
+
+impl AsRef<wgpu::RenderPipeline> for Pipeline {
+    fn as_ref(&self) -> &wgpu::RenderPipeline {
+        &self.wgpu
+    }
+}
+
+impl AsRef<wgpu::ComputePipeline> for ComputePipeline {
+    fn as_ref(&self) -> &wgpu::ComputePipeline {
+        &self.wgpu
+    }
+}
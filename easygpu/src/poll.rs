@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Controls how a `Renderer` polls its device.
+///
+/// wgpu only invokes callbacks, such as buffer mapping and
+/// `Renderer::on_frame_complete`, while the device is being polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PollStrategy {
+    /// The device is only polled when easygpu needs to block, such as in
+    /// `Renderer::read`. Call `Renderer::poll` once per frame to drive other
+    /// callbacks.
+    #[default]
+    Manual,
+    /// A background thread polls the device at the given interval.
+    Background(Duration),
+}
+
+/// A thread that polls a device until dropped.
+#[derive(Debug)]
+pub(crate) struct PollThread {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PollThread {
+    pub fn spawn(device: wgpu::Device, interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = std::thread::Builder::new()
+            .name(String::from("easygpu-poll"))
+            .spawn(move || {
+                while !thread_stop.load(Ordering::Relaxed) {
+                    device.poll(wgpu::Maintain::Poll);
+                    std::thread::sleep(interval);
+                }
+            })
+            .expect("error spawning poll thread");
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for PollThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
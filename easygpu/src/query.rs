@@ -0,0 +1,93 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::renderer::Pass;
+
+/// The statistics collected for each render pass. Results are written in
+/// this order, one `u64` each.
+pub(crate) const STATISTICS: wgpu::PipelineStatisticsTypes =
+    wgpu::PipelineStatisticsTypes::VERTEX_SHADER_INVOCATIONS
+        .union(wgpu::PipelineStatisticsTypes::CLIPPER_INVOCATIONS)
+        .union(wgpu::PipelineStatisticsTypes::CLIPPER_PRIMITIVES_OUT)
+        .union(wgpu::PipelineStatisticsTypes::FRAGMENT_SHADER_INVOCATIONS);
+
+/// The size in bytes of one resolved query.
+pub(crate) const RESULT_SIZE: u64 = 4 * std::mem::size_of::<u64>() as u64;
+
+/// Counts collected by the GPU while a render pass executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PipelineStatistics {
+    pub vertex_shader_invocations: u64,
+    /// The number of primitives sent to the clipper.
+    pub clipper_invocations: u64,
+    /// The number of primitives that survived clipping.
+    pub clipper_primitives_out: u64,
+    pub fragment_shader_invocations: u64,
+}
+
+impl PipelineStatistics {
+    pub(crate) fn from_results(results: &[u64]) -> Vec<Self> {
+        results
+            .chunks_exact(4)
+            .map(|result| Self {
+                vertex_shader_invocations: result[0],
+                clipper_invocations: result[1],
+                clipper_primitives_out: result[2],
+                fragment_shader_invocations: result[3],
+            })
+            .collect()
+    }
+}
+
+/// A set of pipeline statistics queries, one per render pass, created by
+/// `Device::create_statistics_query`.
+///
+/// Record passes with `Frame::pass_with_stats`, then call
+/// `Frame::resolve_statistics` before submitting the frame and
+/// `Renderer::read_statistics` after.
+#[derive(Debug)]
+pub struct StatisticsQuery {
+    pub wgpu: wgpu::QuerySet,
+    pub(crate) resolve: wgpu::Buffer,
+    pub(crate) readback: wgpu::Buffer,
+    pub count: u32,
+}
+
+impl StatisticsQuery {
+    /// Records copying the results of every query into the readback buffer.
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.wgpu, 0..self.count, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, self.resolve.size());
+    }
+}
+
+impl AsRef<wgpu::QuerySet> for StatisticsQuery {
+    fn as_ref(&self) -> &wgpu::QuerySet {
+        &self.wgpu
+    }
+}
+
+/// A render pass collecting pipeline statistics, returned by
+/// `Frame::pass_with_stats`. The query ends when this is dropped.
+pub struct StatisticsPass<'a> {
+    pub(crate) pass: Pass<'a>,
+}
+
+impl<'a> Deref for StatisticsPass<'a> {
+    type Target = Pass<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pass
+    }
+}
+
+impl DerefMut for StatisticsPass<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pass
+    }
+}
+
+impl Drop for StatisticsPass<'_> {
+    fn drop(&mut self) {
+        self.pass.end_pipeline_statistics_query();
+    }
+}
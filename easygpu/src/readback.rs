@@ -0,0 +1,197 @@
+//! Converting framebuffer texels read back with `Renderer::read_to_vec`.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use crate::color::{Bgra8, Rgba, Rgba8};
+
+/// Options for `Renderer::read_to_vec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReadOptions {
+    /// Returns rows bottom-up instead of wgpu's top-down order, as expected by
+    /// image formats and APIs with a bottom-left origin, such as OpenGL.
+    pub flip_y: bool,
+}
+
+impl ReadOptions {
+    pub const fn flipped() -> Self {
+        Self { flip_y: true }
+    }
+}
+
+/// A texel type `Renderer::read_to_vec` can convert framebuffer texels to.
+pub trait ReadTexel: Sized {
+    fn from_bgra8(texel: Bgra8) -> Self;
+}
+
+impl ReadTexel for Bgra8 {
+    fn from_bgra8(texel: Bgra8) -> Self {
+        texel
+    }
+}
+
+/// The channel order used by most image encoders.
+impl ReadTexel for Rgba8 {
+    fn from_bgra8(texel: Bgra8) -> Self {
+        texel.into()
+    }
+}
+
+/// Linear floating point channels. The bytes read back are treated as
+/// sRGB-encoded, as they are in sRGB framebuffers and surfaces.
+impl ReadTexel for Rgba {
+    fn from_bgra8(texel: Bgra8) -> Self {
+        Rgba::from(Rgba8::from(texel)).to_linear()
+    }
+}
+
+/// Resolves once the `map_async` callback of a buffer slice has run, which
+/// only happens while the device is polled.
+#[derive(Debug)]
+pub(crate) struct MapFuture {
+    state: Arc<Mutex<MapState>>,
+}
+
+#[derive(Debug, Default)]
+struct MapState {
+    result: Option<Result<(), wgpu::BufferAsyncError>>,
+    waker: Option<Waker>,
+}
+
+impl MapFuture {
+    /// Maps `slice` for reading.
+    pub fn read(slice: wgpu::BufferSlice<'_>) -> Self {
+        let state = Arc::new(Mutex::new(MapState::default()));
+        let callback_state = state.clone();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let mut state = callback_state.lock().unwrap();
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+        Self { state }
+    }
+}
+
+impl Future for MapFuture {
+    type Output = Result<(), wgpu::BufferAsyncError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The bytes per row of a texture-to-buffer copy of rows of `row_bytes`,
+/// which wgpu requires to be a multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`.
+pub(crate) const fn padded_row_bytes(row_bytes: u32) -> u32 {
+    row_bytes.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
+
+/// Keeps the first `row_bytes` of each row of `padded_row_bytes`.
+pub(crate) fn strip_padding(bytes: &[u8], row_bytes: usize, padded_row_bytes: usize) -> Vec<u8> {
+    bytes
+        .chunks_exact(padded_row_bytes)
+        .flat_map(|row| &row[..row_bytes])
+        .copied()
+        .collect()
+}
+
+/// Copies the texels of a readback buffer with rows of `padded_row_bytes`
+/// into BGRA texels, reordering channels if the framebuffer was `rgba`.
+pub(crate) fn unpad(
+    bytes: &[u8],
+    width: usize,
+    padded_row_bytes: usize,
+    rgba: bool,
+    flip_y: bool,
+) -> Vec<Bgra8> {
+    let mut rows = bytes
+        .chunks_exact(padded_row_bytes)
+        .map(|row| &row[..width * 4])
+        .collect::<Vec<_>>();
+    if flip_y {
+        rows.reverse();
+    }
+
+    rows.into_iter()
+        .flat_map(|row| row.chunks_exact(4))
+        .map(|texel| match *texel {
+            [r, g, b, a] if rgba => Bgra8::new(b, g, r, a),
+            [b, g, r, a] => Bgra8::new(b, g, r, a),
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIGNMENT: usize = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+
+    /// Rows of `width` BGRA texels whose bytes count up from the row index
+    /// times 16, followed by `0xFF` padding.
+    fn padded_rows(width: usize, height: usize, padded_row_bytes: usize) -> Vec<u8> {
+        (0..height)
+            .flat_map(|row| {
+                (0..padded_row_bytes).map(move |byte| {
+                    if byte < width * 4 {
+                        (row * 16 + byte) as u8
+                    } else {
+                        0xFF
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_are_padded_to_the_alignment() {
+        assert_eq!(padded_row_bytes(0), 0);
+        assert_eq!(padded_row_bytes(12), ALIGNMENT as u32);
+        assert_eq!(padded_row_bytes(ALIGNMENT as u32), ALIGNMENT as u32);
+        assert_eq!(padded_row_bytes(ALIGNMENT as u32 + 4), 2 * ALIGNMENT as u32);
+    }
+
+    #[test]
+    fn aligned_rows_are_unchanged() {
+        let width = ALIGNMENT / 4;
+        let bytes = padded_rows(width, 3, ALIGNMENT);
+        assert_eq!(strip_padding(&bytes, width * 4, ALIGNMENT), bytes);
+        let texels = unpad(&bytes, width, ALIGNMENT, false, false);
+        assert_eq!(texels.len(), width * 3);
+        assert_eq!(bytemuck::cast_slice::<Bgra8, u8>(&texels), bytes);
+    }
+
+    #[test]
+    fn unaligned_rows_drop_their_padding() {
+        let bytes = padded_rows(3, 2, ALIGNMENT);
+        let expected = [(0..12).collect::<Vec<u8>>(), (16..28).collect()].concat();
+        assert_eq!(strip_padding(&bytes, 12, ALIGNMENT), expected);
+        let texels = unpad(&bytes, 3, ALIGNMENT, false, false);
+        assert_eq!(bytemuck::cast_slice::<Bgra8, u8>(&texels), expected);
+    }
+
+    #[test]
+    fn unpad_flips_and_reorders() {
+        let bytes = padded_rows(1, 2, ALIGNMENT);
+        assert_eq!(
+            unpad(&bytes, 1, ALIGNMENT, false, true),
+            [Bgra8::new(16, 17, 18, 19), Bgra8::new(0, 1, 2, 3)]
+        );
+        assert_eq!(
+            unpad(&bytes, 1, ALIGNMENT, true, false),
+            [Bgra8::new(2, 1, 0, 3), Bgra8::new(18, 17, 16, 19)]
+        );
+    }
+}
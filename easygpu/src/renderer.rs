@@ -1,32 +1,55 @@
-use std::ops::Range;
-use std::sync::{Arc, Mutex};
-
-use figures::{Size, Rect};
-use wgpu::{FilterMode, MultisampleState, TextureAspect, TextureFormat, TextureViewDescriptor};
-
-use crate::binding::{Bind, BindingGroup, BindingGroupLayout};
-use crate::blending::Blending;
-use crate::buffers::{DepthBuffer, Framebuffer, IndexBuffer, UniformBuffer, VertexBuffer};
-use crate::canvas::Canvas;
-use crate::color::{Bgra8, Rgba};
-use crate::device::{Device, DeviceBuilder};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut, Range};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use figures::{Point, Rect, Size};
+use wgpu::{SubmissionIndex, TextureAspect, TextureFormat, TextureViewDescriptor};
+
+use crate::binding::BindingGroup;
+use crate::buffers::{DepthBuffer, DepthMode, Framebuffer, IndexBuffer, VertexBuffer};
+use crate::canvas::{Canvas, Texel};
+use crate::color::{Bgra8, ColorSpace, Rgba};
+use crate::context::GpuContext;
+use crate::device::{Device, DeviceBuilder, SurfaceOptions};
+use crate::error::{Error, SurfaceConfigError, TargetFormatError};
+use crate::filter::{FilterKind, FilterPipelines};
 use crate::frame::Frame;
-use crate::pipeline::AbstractPipeline;
-use crate::sampler::Sampler;
-use crate::texture::Texture;
-use crate::vertex::VertexLayout;
+use crate::mesh::Mesh;
+use crate::picking::IdBuffer;
+use crate::pipeline::{Pipeline, PipelineCore, PipelineVariant, PreparePipeline};
+use crate::poll::{PollStrategy, PollThread};
+use crate::query::{PipelineStatistics, StatisticsQuery};
+use crate::readback::{self, MapFuture, ReadOptions, ReadTexel};
+use crate::retired::Retired;
+use crate::texture::{Origin, SubResource, Texture, TextureViewHandle};
+use crate::timing::{FrameClock, FrameTimings};
 
 pub trait Draw {
     fn draw<'a>(&'a self, binding: &'a BindingGroup, pass: &mut wgpu::RenderPass<'a>);
 }
 
-
 pub struct RendererBuilder<'a> {
     surface: Option<wgpu::Surface<'a>>,
     instance: Option<wgpu::Instance>,
     adapter: Option<wgpu::Adapter>,
     sample_count: u32,
     offscreen: bool,
+    poll_strategy: PollStrategy,
+    depth_format: TextureFormat,
+    depth_mode: DepthMode,
+    features: wgpu::Features,
+    color_space: ColorSpace,
+    power_preference: wgpu::PowerPreference,
+    backends: wgpu::Backends,
+    max_frames_in_flight: Option<u32>,
+}
+
+impl Default for RendererBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> RendererBuilder<'a> {
@@ -37,60 +60,289 @@ impl<'a> RendererBuilder<'a> {
             adapter: None,
             sample_count: 0,
             offscreen: false,
+            poll_strategy: PollStrategy::default(),
+            depth_format: DepthBuffer::FORMAT,
+            depth_mode: DepthMode::default(),
+            features: wgpu::Features::empty(),
+            color_space: ColorSpace::default(),
+            power_preference: wgpu::PowerPreference::default(),
+            backends: wgpu::Backends::all(),
+            max_frames_in_flight: None,
         }
     }
 
-    pub fn with_surface(mut self, surface: wgpu::Surface<'a>, instance: wgpu::Instance, sample_count: u32) -> Self {
+    /// Lists the adapters of `instance` on `backends`, such as to let users
+    /// choose a GPU. A choice can be applied with `with_backends` and
+    /// `with_power_preference`, or by passing the adapter itself to
+    /// `with_offscreen`.
+    ///
+    /// Adapters can't be enumerated on the web, so this isn't available there.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn enumerate_adapters(
+        instance: &wgpu::Instance,
+        backends: wgpu::Backends,
+    ) -> Vec<wgpu::AdapterInfo> {
+        instance
+            .enumerate_adapters(backends)
+            .iter()
+            .map(wgpu::Adapter::get_info)
+            .collect()
+    }
+
+    pub fn with_surface(
+        mut self,
+        surface: wgpu::Surface<'a>,
+        instance: wgpu::Instance,
+        sample_count: u32,
+    ) -> Self {
         self.surface = Some(surface);
         self.instance = Some(instance);
         self.sample_count = sample_count;
         self
     }
 
-    pub fn with_offscreen(mut self, offscreen: bool, adapter: wgpu::Adapter, sample_count: u32) -> Self {
+    pub fn with_offscreen(
+        mut self,
+        offscreen: bool,
+        adapter: wgpu::Adapter,
+        sample_count: u32,
+    ) -> Self {
         self.offscreen = offscreen;
         self.adapter = Some(adapter);
         self.sample_count = sample_count;
         self
     }
 
-    pub async fn build(self) -> Result<Renderer<'a>, wgpu::RequestDeviceError> {
-        if self.offscreen {
-            let adapter = self.adapter.unwrap();
-            let device = DeviceBuilder::new(adapter).build().await?;
+    pub fn with_poll_strategy(mut self, poll_strategy: PollStrategy) -> Self {
+        self.poll_strategy = poll_strategy;
+        self
+    }
+
+    /// Sets the format used for every depth buffer and pipeline. Defaults to
+    /// `DepthBuffer::FORMAT`.
+    pub fn with_depth_format(mut self, depth_format: TextureFormat) -> Self {
+        self.depth_format = depth_format;
+        self
+    }
+
+    /// Sets how depth values are compared and cleared.
+    pub fn with_depth_mode(mut self, depth_mode: DepthMode) -> Self {
+        self.depth_mode = depth_mode;
+        self
+    }
+
+    /// Sets the optional wgpu features to request. See
+    /// `DeviceBuilder::with_features`.
+    pub fn with_features(mut self, features: wgpu::Features) -> Self {
+        self.features = features;
+        self
+    }
 
-            Ok(Renderer { device, sample_count: self.sample_count })
+    /// Sets the color space of clear colors, color texels, and lyon vertex
+    /// colors. Defaults to `ColorSpace::Srgb`.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Sets whether to prefer an integrated or a discrete GPU when choosing
+    /// an adapter for the surface. Defaults to `wgpu::PowerPreference::None`.
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Limits the adapter chosen for the surface to `backends`. Defaults to
+    /// every backend the surface's instance was created with.
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    /// Limits how many presented frames the GPU may be working on. See
+    /// `Renderer::set_max_frames_in_flight`.
+    pub fn with_max_frames_in_flight(mut self, max_frames_in_flight: Option<u32>) -> Self {
+        assert_ne!(
+            max_frames_in_flight,
+            Some(0),
+            "at least one frame must be allowed in flight"
+        );
+        self.max_frames_in_flight = max_frames_in_flight;
+        self
+    }
+
+    /// Builds the renderer. The returned future only waits on wgpu, so it can
+    /// be driven by any executor.
+    ///
+    /// Returns `Error::NoAdaptersFound` if no adapter on the allowed backends
+    /// supports the surface.
+    pub async fn build(self) -> Result<Renderer<'a>, Error> {
+        let device = if self.offscreen {
+            let adapter = self.adapter.unwrap();
+            DeviceBuilder::new(adapter)
+                .with_depth_format(self.depth_format)
+                .with_depth_mode(self.depth_mode)
+                .with_features(self.features)
+                .with_sample_count(self.sample_count)
+                .build()
+                .await?
         } else {
             let instance = self.instance.unwrap();
             let surface = self.surface.unwrap();
-            let adapter = instance
-                .request_adapter(&wgpu::RequestAdapterOptions {
-                    compatible_surface: Some(&surface),
-                    power_preference: wgpu::PowerPreference::default(),
-                    force_fallback_adapter: false,
-                })
+            let adapter = select_adapter(&instance, &surface, self.power_preference, self.backends)
                 .await
-                .unwrap();
+                .ok_or(Error::NoAdaptersFound)?;
 
-            let device = DeviceBuilder::new(adapter)
+            DeviceBuilder::new(adapter)
                 .with_surface(surface)
+                .with_depth_format(self.depth_format)
+                .with_depth_mode(self.depth_mode)
+                .with_features(self.features)
+                .with_sample_count(self.sample_count)
                 .build()
-                .await?;
-            Ok(Renderer { device, sample_count: self.sample_count })
-        }
+                .await?
+        };
+
+        let poller = match self.poll_strategy {
+            PollStrategy::Manual => None,
+            PollStrategy::Background(interval) => {
+                Some(PollThread::spawn(device.wgpu.clone(), interval))
+            }
+        };
+
+        Ok(Renderer {
+            device,
+            poller,
+            uploads: None,
+            clock: FrameClock::default(),
+            retired: Mutex::default(),
+            max_frames_in_flight: self.max_frames_in_flight,
+            in_flight: Mutex::default(),
+            filters: FilterPipelines::default(),
+        })
+    }
+
+    /// Builds the renderer, blocking the current thread until it's ready.
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<Renderer<'a>, Error> {
+        pollster::block_on(self.build())
     }
 }
 
+/// Requests an adapter for `surface`, falling back to the enumerated adapter
+/// that best matches `power_preference` when wgpu's choice isn't on one of
+/// `backends`.
+async fn select_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'_>,
+    power_preference: wgpu::PowerPreference,
+    backends: wgpu::Backends,
+) -> Option<wgpu::Adapter> {
+    let requested = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            compatible_surface: Some(surface),
+            power_preference,
+            force_fallback_adapter: false,
+        })
+        .await;
+    if let Some(adapter) = requested
+        .filter(|adapter| backends.contains(wgpu::Backends::from(adapter.get_info().backend)))
+    {
+        return Some(adapter);
+    }
+
+    fallback_adapter(instance, surface, power_preference, backends)
+}
+
+/// The adapter on `backends` supporting `surface` that best matches
+/// `power_preference`.
+#[cfg(not(target_arch = "wasm32"))]
+fn fallback_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'_>,
+    power_preference: wgpu::PowerPreference,
+    backends: wgpu::Backends,
+) -> Option<wgpu::Adapter> {
+    instance
+        .enumerate_adapters(backends)
+        .into_iter()
+        .filter(|adapter| adapter.is_surface_supported(surface))
+        .min_by_key(|adapter| {
+            use wgpu::DeviceType::*;
+            let order: &[wgpu::DeviceType] = match power_preference {
+                wgpu::PowerPreference::None => &[],
+                wgpu::PowerPreference::LowPower => &[IntegratedGpu, DiscreteGpu, VirtualGpu],
+                wgpu::PowerPreference::HighPerformance => &[DiscreteGpu, IntegratedGpu, VirtualGpu],
+            };
+            let device_type = adapter.get_info().device_type;
+            order
+                .iter()
+                .position(|preferred| *preferred == device_type)
+                .unwrap_or(order.len())
+        })
+}
+
+/// Adapters can't be enumerated on the web, so only wgpu's choice is used.
+#[cfg(target_arch = "wasm32")]
+fn fallback_adapter(
+    _instance: &wgpu::Instance,
+    _surface: &wgpu::Surface<'_>,
+    _power_preference: wgpu::PowerPreference,
+    _backends: wgpu::Backends,
+) -> Option<wgpu::Adapter> {
+    None
+}
+
 #[derive(Debug)]
 pub struct Renderer<'a> {
     pub device: Device<'a>,
-    /// Enables MSAA for values > 1.
-    pub(crate) sample_count: u32,
+    poller: Option<PollThread>,
+    uploads: Option<wgpu::CommandEncoder>,
+    clock: FrameClock,
+    retired: Mutex<Retired<Box<dyn Any + Send>>>,
+    max_frames_in_flight: Option<u32>,
+    /// Submissions of presented frames, oldest first, while
+    /// `max_frames_in_flight` is set.
+    in_flight: Mutex<VecDeque<SubmissionIndex>>,
+    filters: FilterPipelines,
+}
+
+impl AsRef<wgpu::Device> for Renderer<'_> {
+    fn as_ref(&self) -> &wgpu::Device {
+        &self.device.wgpu
+    }
+}
+
+impl AsRef<wgpu::Queue> for Renderer<'_> {
+    fn as_ref(&self) -> &wgpu::Queue {
+        &self.device.queue
+    }
+}
+
+impl Deref for Renderer<'_> {
+    type Target = GpuContext;
+
+    fn deref(&self) -> &GpuContext {
+        &self.device
+    }
 }
 
 impl<'a> Renderer<'a> {
-    pub const fn sample_count(&self) -> u32 {
-        self.sample_count
+    /// The renderer's shared context, for creating resources from other
+    /// threads. Resource creation methods are also available on `Renderer`
+    /// directly, through `Deref`.
+    pub const fn context(&self) -> &Arc<GpuContext> {
+        self.device.context()
+    }
+
+    /// The underlying wgpu device and queue, for work easygpu doesn't wrap.
+    ///
+    /// Resources created this way can be used alongside easygpu's through
+    /// each wrapper's `AsRef` implementation and `from_wgpu` constructor.
+    /// Work submitted directly to the queue is not ordered with frames or
+    /// uploads that haven't been presented yet.
+    pub fn wgpu(&self) -> (&wgpu::Device, &wgpu::Queue) {
+        (&self.device.wgpu, &self.device.queue)
     }
 
     pub fn configure<PresentMode: Into<wgpu::PresentMode>>(
@@ -102,9 +354,45 @@ impl<'a> Renderer<'a> {
         self.device.configure(size, mode, format)
     }
 
+    /// Configures the surface, failing if it doesn't support
+    /// `options.usage`. See `Device::supported_surface_usages`.
+    pub fn configure_with_options<PresentMode: Into<wgpu::PresentMode>>(
+        &mut self,
+        size: Size<u32>,
+        mode: PresentMode,
+        format: TextureFormat,
+        options: &SurfaceOptions,
+    ) -> Result<(), SurfaceConfigError> {
+        self.device
+            .configure_with_options(size, mode, format, options)
+    }
+
+    /// The color space of clear colors, color texels, and lyon vertex colors.
+    pub const fn color_space(&self) -> ColorSpace {
+        self.device.color_space()
+    }
+
+    /// Changes the color space used by frames created after this call.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) {
+        self.device.set_color_space(color_space);
+    }
+
+    /// Returns true if the surface is configured with an sRGB format, which
+    /// converts colors written by shaders from linear to sRGB. Color constants
+    /// written in sRGB, such as those picked from an image editor, need to be
+    /// converted to linear first when this is true.
+    pub fn surface_is_srgb(&self) -> bool {
+        self.device
+            .surface_format()
+            .is_some_and(|format| format.is_srgb())
+    }
+
     pub fn current_frame(&self) -> Result<RenderFrame, wgpu::SurfaceError> {
         let surface = self.device.surface.as_ref().unwrap();
-        let surface_texture = surface.get_current_texture()?;
+        let surface_texture = self.clock.measure(
+            || surface.get_current_texture(),
+            |timings, elapsed| timings.acquire = elapsed,
+        )?;
         let view = surface_texture
             .texture
             .create_view(&TextureViewDescriptor::default());
@@ -115,119 +403,346 @@ impl<'a> Renderer<'a> {
                 .device
                 .create_zbuffer(self.device.size(), self.sample_count),
             size: self.device.size(),
+            clock: self.clock.clone(),
         })
     }
 
-    pub fn texture(
-        &self,
-        size: Size<u32>,
-        format: wgpu::TextureFormat,
-        usage: wgpu::TextureUsages,
-        multisampled: bool,
-    ) -> Texture {
-        let sample_count = if multisampled { self.sample_count } else { 1 };
-        self.device
-            .create_texture(size, format, usage, sample_count)
+    /// Timings of the most recent frame. See `FrameTimings`.
+    pub fn frame_timings(&self) -> FrameTimings {
+        self.clock.timings()
     }
 
-    pub fn framebuffer(
-        &self,
+    /// Renders a single frame into a new single-sample `Framebuffer`.
+    ///
+    /// When MSAA is enabled, a multisampled texture is created for the pass
+    /// and resolved into the returned framebuffer. The commands are submitted
+    /// before returning; call `wait_idle` to block until they complete.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = size.width,
+                height = size.height,
+                ?format,
+            )
+        )
+    )]
+    pub fn render_offscreen<F>(
+        &mut self,
         size: Size<u32>,
-        format: wgpu::TextureFormat,
-    ) -> Framebuffer {
-        self.device
-            .create_framebuffer(size, format, self.sample_count)
-    }
+        format: TextureFormat,
+        render: F,
+    ) -> Framebuffer
+    where
+        F: FnOnce(&mut OffscreenFrame<'_>),
+    {
+        let framebuffer = Framebuffer {
+            texture: self.device.create_texture(
+                size,
+                format,
+                wgpu::TextureUsages::TEXTURE_BINDING
+                    | wgpu::TextureUsages::COPY_DST
+                    | wgpu::TextureUsages::COPY_SRC
+                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
+                1,
+            ),
+            depth: self.zbuffer(size),
+        };
+        let multisample = (self.sample_count > 1)
+            .then(|| self.texture(size, format, wgpu::TextureUsages::RENDER_ATTACHMENT, true));
 
-    pub fn zbuffer(&self, size: Size<u32>) -> DepthBuffer {
-        self.device.create_zbuffer(size, self.sample_count)
+        let mut frame = self.frame();
+        render(&mut OffscreenFrame {
+            frame: &mut frame,
+            target: &framebuffer,
+            multisample: multisample.as_ref().map(|texture| &texture.view),
+        });
+        self.present(frame);
+
+        framebuffer
     }
 
-    pub fn vertex_buffer<T: bytemuck::Pod>(&self, verts: &[T]) -> VertexBuffer
+    /// Invokes `callback` once the GPU has finished everything submitted so
+    /// far, including the most recently presented frame.
+    ///
+    /// Work submitted after this call isn't waited on. There is no variant for
+    /// a single `SubmissionIndex`, since wgpu can only report its completion
+    /// by blocking: to wait for a specific submission, call `poll` with
+    /// `wgpu::Maintain::WaitForSubmissionIndex`.
+    /// Callbacks are only invoked while the device is polled; see
+    /// `PollStrategy`.
+    pub fn on_frame_complete<F>(&self, callback: F)
     where
-        T: 'static + Copy,
+        F: FnOnce() + Send + 'static,
     {
-        self.device.create_buffer(verts)
+        self.device.queue.on_submitted_work_done(callback);
     }
 
-    pub fn uniform_buffer<T>(&self, buf: &[T]) -> UniformBuffer
-    where
-        T: bytemuck::Pod + 'static + Copy,
-    {
-        self.device.create_uniform_buffer(buf)
+    /// Polls the device, invoking any callbacks for completed work.
+    pub fn poll(&self, maintain: wgpu::Maintain) -> wgpu::MaintainResult {
+        let result = self.device.wgpu.poll(maintain);
+        self.retired().collect();
+        result
     }
 
-    pub fn binding_group(&self, layout: &BindingGroupLayout, binds: &[&dyn Bind]) -> BindingGroup {
-        self.device.create_binding_group(layout, binds)
+    /// Keeps `resource` alive until the GPU has finished the next presented
+    /// submission, which includes every frame recorded so far, then drops it.
+    ///
+    /// Use this when replacing resources that frames may still be drawing
+    /// with, such as textures evicted from a cache. Retired resources are
+    /// dropped while presenting and polling.
+    pub fn retire<T: Send + 'static>(&self, resource: T) {
+        self.retired().retire(Box::new(resource));
     }
 
-    pub fn sampler(&self, min_filter: FilterMode, mag_filter: FilterMode) -> Sampler {
-        self.device.create_sampler(min_filter, mag_filter)
+    /// The number of retired resources that haven't been dropped yet.
+    pub fn retired_len(&self) -> usize {
+        self.retired().len()
     }
 
-    pub fn pipeline<T>(&self, blending: Blending, format: TextureFormat) -> T
-    where
-        T: AbstractPipeline<'static>,
-    {
-        let desc = T::description();
-        let pip_layout = self.device.create_pipeline_layout(desc.pipeline_layout);
-        let vertex_layout = VertexLayout::from(desc.vertex_layout);
-        let shader = self.device.create_shader(desc.shader);
-
-        T::setup(
-            self.device.create_pipeline(
-                pip_layout,
-                vertex_layout,
-                blending,
-                &shader,
-                format,
-                MultisampleState {
-                    count: self.sample_count,
-                    mask: !0,
-                    alpha_to_coverage_enabled: false,
-                },
-            ),
-            &self.device,
-        )
+    /// The most presented frames the GPU may be working on before `frame`
+    /// blocks, or `None` if frames are only limited by the surface.
+    pub const fn max_frames_in_flight(&self) -> Option<u32> {
+        self.max_frames_in_flight
+    }
+
+    /// Limits how many presented frames the GPU may be working on. When the
+    /// limit is reached, `frame` blocks until the oldest one completes, so
+    /// the next frame is built from more recent input. `None`, the default,
+    /// removes the limit.
+    ///
+    /// This is independent of `SurfaceOptions::maximum_frame_latency`, which
+    /// limits frames queued for display, and also applies to offscreen
+    /// rendering. The time spent waiting is reported as
+    /// `FrameTimings::throttle`. Panics if the limit is zero.
+    pub fn set_max_frames_in_flight(&mut self, max_frames_in_flight: Option<u32>) {
+        assert_ne!(
+            max_frames_in_flight,
+            Some(0),
+            "at least one frame must be allowed in flight"
+        );
+        self.max_frames_in_flight = max_frames_in_flight;
+        if max_frames_in_flight.is_none() {
+            self.in_flight().clear();
+        }
+    }
+
+    fn in_flight(&self) -> MutexGuard<'_, VecDeque<SubmissionIndex>> {
+        // The queue only holds indices to wait on, which are valid in any
+        // state.
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
+    /// Blocks until fewer than `max_frames_in_flight` presented frames are
+    /// still running. The queue isn't locked while waiting.
+    fn throttle(&self, max_frames_in_flight: u32) {
+        loop {
+            let index = {
+                let mut in_flight = self.in_flight();
+                if in_flight.len() < max_frames_in_flight as usize {
+                    break;
+                }
+                in_flight.pop_front().unwrap()
+            };
+            self.poll(wgpu::Maintain::WaitForSubmissionIndex(index));
+        }
+    }
+
+    fn retired(&self) -> MutexGuard<'_, Retired<Box<dyn Any + Send>>> {
+        // Retired resources are only ever added or dropped, so a panic while
+        // holding the lock can't leave them inconsistent.
+        self.retired
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns true if a background thread is polling the device.
+    pub fn is_polled_in_background(&self) -> bool {
+        self.poller.is_some()
+    }
+
+    /// Blocks until all submitted work has completed.
+    pub fn wait_idle(&self) {
+        self.poll(wgpu::Maintain::Wait);
+    }
+
+    /// Reads the texels of `fb`, which must be an 8-bit RGBA or BGRA
+    /// framebuffer, passing them to `f` in BGRA order. Multisampled
+    /// framebuffers are resolved first. See `read_texture` for other
+    /// textures.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = fb.width(),
+                height = fb.height(),
+            )
+        )
+    )]
     pub fn read<F>(&mut self, fb: &Framebuffer, f: F) -> Result<(), wgpu::BufferAsyncError>
     where
         F: 'static + FnOnce(&[Bgra8]),
     {
+        let texels = self.read_bgra(fb, false)?;
+        f(&texels);
+        Ok(())
+    }
+
+    /// Reads the texels of `fb` like `read`, converting each to `T`, such as
+    /// `Rgba8` for image encoders or `Rgba` for linear colors.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = fb.width(),
+                height = fb.height(),
+            )
+        )
+    )]
+    pub fn read_to_vec<T: ReadTexel>(
+        &mut self,
+        fb: &Framebuffer,
+        options: ReadOptions,
+    ) -> Result<Vec<T>, wgpu::BufferAsyncError> {
+        Ok(self
+            .read_bgra(fb, options.flip_y)?
+            .into_iter()
+            .map(T::from_bgra8)
+            .collect())
+    }
+
+    /// Reads the texels of `rect`, measured from the top left of the first
+    /// layer's first mip level, as rows of bytes in the texture's format
+    /// without padding.
+    ///
+    /// Only the rect is copied back. Any format with a single aspect and
+    /// uncompressed texels can be read, and texels are returned as stored,
+    /// without channel reordering or color conversion. Multisampled textures
+    /// are first resolved into a temporary texture, which requires
+    /// `TextureUsages::RENDER_ATTACHMENT` and a resolvable color format;
+    /// other textures need `TextureUsages::COPY_SRC`. Panics if the texture
+    /// can't be read or `rect` isn't inside it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                width = rect.size.width,
+                height = rect.size.height,
+                format = ?texture.format,
+            )
+        )
+    )]
+    pub fn read_texture(
+        &mut self,
+        texture: &Texture,
+        rect: Rect<u32>,
+    ) -> Result<Vec<u8>, wgpu::BufferAsyncError> {
+        let format = texture.format;
+        let texel_bytes = match (format.block_dimensions(), format.block_copy_size(None)) {
+            ((1, 1), Some(texel_bytes)) => texel_bytes,
+            _ => panic!("Renderer::read_texture can't read {format:?} textures"),
+        };
+        assert!(
+            rect.origin
+                .x
+                .checked_add(rect.size.width)
+                .is_some_and(|end| end <= texture.size.width)
+                && rect
+                    .origin
+                    .y
+                    .checked_add(rect.size.height)
+                    .is_some_and(|end| end <= texture.size.height),
+            "rect {rect:?} is outside of the {}x{} texture",
+            texture.size.width,
+            texture.size.height
+        );
+        if rect.size.width == 0 || rect.size.height == 0 {
+            return Ok(Vec::new());
+        }
+
         let mut encoder = self.device.create_command_encoder();
+        let usage = texture.wgpu.usage();
+        let resolved;
+        let source = if texture.wgpu.sample_count() > 1 {
+            assert!(
+                usage.contains(wgpu::TextureUsages::RENDER_ATTACHMENT),
+                "multisampled textures need TextureUsages::RENDER_ATTACHMENT to be read"
+            );
+            resolved = self.device.create_texture(
+                texture.size,
+                format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                1,
+            );
+            // An empty pass that keeps the samples and resolves them.
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &texture.view,
+                    resolve_target: Some(&resolved.view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                ..Default::default()
+            });
+            &resolved.wgpu
+        } else {
+            assert!(
+                usage.contains(wgpu::TextureUsages::COPY_SRC),
+                "textures need TextureUsages::COPY_SRC to be read"
+            );
+            &texture.wgpu
+        };
 
-        let bytesize = 4 * fb.size();
+        let row_bytes = texel_bytes * rect.size.width;
+        let padded_row_bytes = readback::padded_row_bytes(row_bytes);
+        let bytesize = u64::from(padded_row_bytes) * u64::from(rect.size.height);
         let gpu_buffer = self.device.wgpu.create_buffer(&wgpu::BufferDescriptor {
             label: None,
-            size: bytesize as u64,
+            size: bytesize,
             usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
         encoder.copy_texture_to_buffer(
             wgpu::TexelCopyTextureInfo {
-                texture: &fb.texture.wgpu,
+                texture: source,
                 mip_level: 0,
-                origin: wgpu::Origin3d { x: 0, y: 0, z: 0 },
+                origin: wgpu::Origin3d {
+                    x: rect.origin.x,
+                    y: rect.origin.y,
+                    z: 0,
+                },
                 aspect: TextureAspect::All,
             },
             wgpu::TexelCopyBufferInfo {
                 buffer: &gpu_buffer,
                 layout: wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    // TODO: Must be a multiple of 256
-                    bytes_per_row: Some(4 * fb.texture.size.width),
-                    rows_per_image: Some(fb.texture.size.height),
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(rect.size.height),
                 },
             },
-            fb.texture.extent,
+            wgpu::Extent3d {
+                width: rect.size.width,
+                height: rect.size.height,
+                depth_or_array_layers: 1,
+            },
         );
-        // let submission_index = self.device.submit(vec![encoder.finish()]);
+        let submission_index = self.device.submit(vec![encoder.finish()]);
 
-        let mut buffer: Vec<u8> = Vec::with_capacity(bytesize);
-
-        let dst = gpu_buffer.slice(0..bytesize as u64);
+        let dst = gpu_buffer.slice(..);
         let result = Arc::new(Mutex::new(None));
         let callback_result = result.clone();
         dst.map_async(wgpu::MapMode::Read, move |map_result| {
@@ -235,41 +750,194 @@ impl<'a> Renderer<'a> {
             *result = Some(map_result);
         });
 
-        // let mut queue_empty = self
-        //     .device
-        //     .wgpu
-        //     .poll(wgpu::MaintainBase::WaitForSubmissionIndex(submission_index));
+        self.wait_for_map(&result, submission_index)?;
+
+        let view = dst.get_mapped_range();
+        let bytes = readback::strip_padding(&view, row_bytes as usize, padded_row_bytes as usize);
+        drop(view);
+        gpu_buffer.unmap();
+
+        Ok(bytes)
+    }
+
+    fn read_bgra(
+        &mut self,
+        fb: &Framebuffer,
+        flip_y: bool,
+    ) -> Result<Vec<Bgra8>, wgpu::BufferAsyncError> {
+        let format = fb.texture.format;
+        let rgba = matches!(
+            format,
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb
+        );
+        assert!(
+            rgba || matches!(
+                format,
+                TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+            ),
+            "Renderer::read requires an 8-bit RGBA or BGRA framebuffer, not {format:?}"
+        );
+
+        let size = fb.texture.size;
+        let bytes = self.read_texture(&fb.texture, Rect::new(Point::default(), size))?;
+        Ok(readback::unpad(
+            &bytes,
+            size.width as usize,
+            4 * size.width as usize,
+            rgba,
+            flip_y,
+        ))
+    }
+
+    /// Blocks until `submission_index` completes and the map callback storing
+    /// into `result` has run.
+    fn wait_for_map(
+        &self,
+        result: &Mutex<Option<Result<(), wgpu::BufferAsyncError>>>,
+        submission_index: SubmissionIndex,
+    ) -> Result<(), wgpu::BufferAsyncError> {
+        self.poll(wgpu::Maintain::WaitForSubmissionIndex(submission_index));
         loop {
             let result = result.lock().unwrap().take();
             match result {
-                Some(Ok(())) => break,
-                Some(Err(err)) => return Err(err),
+                Some(result) => return result,
                 None => {
                     // We didn't get our map callback, but the submission is done.
                     // We'll keep polling the device until we get our map callback.
-                    // queue_empty = self.device.wgpu.poll(wgpu::MaintainBase::Poll);
+                    self.poll(wgpu::Maintain::Poll);
                 }
             }
         }
+    }
 
-        let view = dst.get_mapped_range();
-        buffer.extend_from_slice(&view);
-        if buffer.len() == bytesize {
-            let (head, body, tail) = unsafe { buffer.align_to::<Bgra8>() };
-            if !(head.is_empty() && tail.is_empty()) {
-                panic!("Renderer::read: framebuffer is not a valid Bgra8 buffer");
-            }
-            f(body);
+    /// Reads the results of `query`, one per query in the set. The frame
+    /// that called `Frame::resolve_statistics` must already be presented.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(count = query.count))
+    )]
+    pub fn read_statistics(
+        &mut self,
+        query: &StatisticsQuery,
+    ) -> Result<Vec<PipelineStatistics>, wgpu::BufferAsyncError> {
+        // Submitting an empty batch gives us an index that follows the frame.
+        let submission_index = self.device.submit(std::iter::empty());
+
+        let slice = query.readback.slice(..);
+        let result = Arc::new(Mutex::new(None));
+        let callback_result = result.clone();
+        slice.map_async(wgpu::MapMode::Read, move |map_result| {
+            *callback_result.lock().unwrap() = Some(map_result);
+        });
+        self.wait_for_map(&result, submission_index)?;
+
+        let view = slice.get_mapped_range();
+        let statistics = PipelineStatistics::from_results(bytemuck::cast_slice(&view));
+        drop(view);
+        query.readback.unmap();
+
+        Ok(statistics)
+    }
+
+    /// Reads the object id at `x`, `y` of `ids` without blocking. The future
+    /// resolves to `None` if the position is outside of the buffer or no
+    /// object was drawn there.
+    ///
+    /// Only the single texel is copied back, and the copy is submitted before
+    /// this returns, so the future doesn't borrow the renderer and rendering
+    /// can continue while it's pending. It resolves once the copy completes
+    /// and the device is polled, either by `poll` or by a background thread;
+    /// see `PollStrategy`. The frame that rendered `ids` must already be
+    /// presented.
+    ///
+    /// Each pick copies into its own small buffer, so any number can be
+    /// pending at once, such as when hit-testing on every mouse move, and
+    /// dropping the future before it resolves releases its buffer.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(x = x, y = y))
+    )]
+    pub fn pick(
+        &self,
+        ids: &IdBuffer,
+        x: u32,
+        y: u32,
+    ) -> impl Future<Output = Result<Option<u32>, wgpu::BufferAsyncError>> + Send + 'static {
+        let size = ids.size();
+        let readback = (x < size.width && y < size.height).then(|| {
+            let buffer = self.device.wgpu.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: wgpu::COPY_BUFFER_ALIGNMENT,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.copy_id(ids, x, y, &buffer);
+            let mapped = MapFuture::read(buffer.slice(..));
+            (buffer, mapped)
+        });
+
+        async move {
+            let Some((buffer, mapped)) = readback else {
+                return Ok(None);
+            };
+            mapped.await?;
+            let id = bytemuck::pod_read_unaligned::<u32>(&buffer.slice(..).get_mapped_range()[..4]);
+            buffer.unmap();
+            Ok((id != IdBuffer::NONE).then_some(id))
         }
+    }
 
-        gpu_buffer.unmap();
+    /// Reads the object id at `x`, `y` of `ids` like `pick`, blocking the
+    /// current thread until the texel has been copied back.
+    #[cfg(feature = "blocking")]
+    pub fn pick_blocking(
+        &self,
+        ids: &IdBuffer,
+        x: u32,
+        y: u32,
+    ) -> Result<Option<u32>, wgpu::BufferAsyncError> {
+        let mut pick = std::pin::pin!(self.pick(ids, x, y));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        self.poll(wgpu::Maintain::Wait);
+        loop {
+            if let std::task::Poll::Ready(id) = pick.as_mut().poll(&mut cx) {
+                return id;
+            }
+            // The copy is done, but the map callback may need another poll.
+            self.poll(wgpu::Maintain::Poll);
+        }
+    }
 
-        Ok(())
+    /// Submits a copy of the texel at `x`, `y` of `ids` into `buffer`.
+    fn copy_id(&self, ids: &IdBuffer, x: u32, y: u32, buffer: &wgpu::Buffer) {
+        let mut encoder = self.device.create_command_encoder();
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &ids.texture.wgpu,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: None,
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.device.submit(Some(encoder.finish()));
     }
 
     pub fn update_pipeline<'b, T>(&mut self, pip: &'b T, p: T::PrepareContext)
     where
-        T: AbstractPipeline<'b>,
+        T: PreparePipeline<'b>,
     {
         if let Some((buffer, uniforms)) = pip.prepare(p) {
             self.device
@@ -277,16 +945,132 @@ impl<'a> Renderer<'a> {
         }
     }
 
-    pub fn frame(&mut self) -> Frame {
+    /// Creates a frame to record commands into.
+    ///
+    /// Several frames can be recorded at once, including from different
+    /// threads, and then submitted together in order with `present_all`.
+    ///
+    /// When the renderer limits its frames in flight, this blocks until an
+    /// earlier frame completes. See `set_max_frames_in_flight`.
+    pub fn frame(&self) -> Frame {
+        if let Some(max_frames_in_flight) = self.max_frames_in_flight {
+            self.clock.measure(
+                || self.throttle(max_frames_in_flight),
+                |timings, elapsed| timings.throttle = elapsed,
+            );
+        }
+        self.clock.start_encode();
         let encoder = self.device.create_command_encoder();
-        Frame::new(encoder)
+        let mut frame = Frame::new(encoder);
+        frame.depth_mode = self.device.depth_mode();
+        frame.color_space = self.device.color_space();
+        frame
+    }
+
+    /// Submits the frame, preceded by any pending uploads.
+    pub fn present(&mut self, frame: Frame) -> SubmissionIndex {
+        self.present_all([frame])
+    }
+
+    /// Submits several frames to the queue at once, in order.
+    ///
+    /// This is cheaper than calling `present` for each frame when one logical
+    /// frame is built from several, such as a shadow pass, a main pass, and a
+    /// UI pass.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn present_all<I: IntoIterator<Item = Frame>>(&mut self, frames: I) -> SubmissionIndex {
+        let uploads = self.uploads.take().map(wgpu::CommandEncoder::finish);
+        let index = self.device.submit(
+            uploads
+                .into_iter()
+                .chain(frames.into_iter().map(|frame| frame.encoder.finish())),
+        );
+        self.clock.finish_encode();
+
+        let mut retired = self.retired();
+        retired.submitted(&self.device.queue, index.clone());
+        retired.collect();
+        drop(retired);
+
+        if self.max_frames_in_flight.is_some() {
+            self.in_flight().push_back(index.clone());
+        }
+
+        index
+    }
+
+    /// The encoder for copies that must be visible to the next presented
+    /// frame. It is submitted ahead of the frames passed to `present` or
+    /// `present_all`, so uploads can be recorded at any point while building
+    /// a frame.
+    pub fn upload_encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        let device = &self.device;
+        self.uploads
+            .get_or_insert_with(|| device.create_command_encoder())
+    }
+
+    /// Records `commands` onto the upload encoder instead of submitting them
+    /// immediately like `submit`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(ops = commands.len()))
+    )]
+    pub fn upload<T: Texel>(&mut self, commands: &[Op<T>]) {
+        let encoder = self
+            .uploads
+            .get_or_insert_with(|| self.device.create_command_encoder());
+        for c in commands.iter() {
+            c.encode(&mut self.device, encoder);
+        }
+    }
+
+    /// Applies an image filter to `src`, writing the result to `dst`, using
+    /// compute shaders.
+    ///
+    /// Like `upload`, the filter is recorded onto the upload encoder, so it
+    /// runs after frames that were already presented and before the next one.
+    /// The textures must be different and the same size. `src` needs
+    /// `TextureUsages::TEXTURE_BINDING` and a filterable format, and `dst`
+    /// needs `TextureUsages::STORAGE_BINDING` and one of the formats
+    /// `Rgba8Unorm`, `Rgba8Snorm` or `Rgba16Float`; anything else panics.
+    /// Textures from `GpuContext::create_storage_texture` work as either.
+    ///
+    /// Bind groups are cached for the textures most recently filtered, which
+    /// keeps those textures alive, and blurs reuse one intermediate texture
+    /// per format.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(
+                ?kind,
+                width = dst.size.width,
+                height = dst.size.height,
+                format = ?dst.format,
+            )
+        )
+    )]
+    pub fn filter(&mut self, kind: FilterKind, src: &Texture, dst: &Texture) {
+        let encoder = self
+            .uploads
+            .get_or_insert_with(|| self.device.create_command_encoder());
+        self.filters.record(&self.device, encoder, kind, src, dst);
     }
 
-    pub fn present(&mut self, frame: Frame) {
-        self.device.submit(vec![frame.encoder.finish()]);
+    /// Submits pending uploads without a frame. Returns `None` if nothing was
+    /// recorded.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+    pub fn flush_uploads(&mut self) -> Option<SubmissionIndex> {
+        let uploads = self.uploads.take()?;
+        Some(self.device.submit([uploads.finish()]))
     }
 
-    pub fn submit<T: Copy>(&mut self, commands: &[Op<T>]) {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(ops = commands.len()))
+    )]
+    pub fn submit<T: Texel>(&mut self, commands: &[Op<T>]) {
         let mut encoder = self.device.create_command_encoder();
         for c in commands.iter() {
             c.encode(&mut self.device, &mut encoder);
@@ -295,24 +1079,39 @@ impl<'a> Renderer<'a> {
     }
 }
 
-pub enum Op<'a, T> {
-    Clear(&'a dyn Canvas<Color = T>, T),
-    Fill(&'a dyn Canvas<Color = T>, &'a [T]),
+pub enum Op<'a, T: Texel> {
+    Clear(&'a dyn Canvas<T>, T),
+    Fill(&'a dyn Canvas<T>, &'a [T]),
     Transfer {
-        f: &'a dyn Canvas<Color = T>,
+        f: &'a dyn Canvas<T>,
+        buf: &'a [T],
+        rect: Rect<i32>,
+    },
+    Blit(&'a dyn Canvas<T>, Rect<u32>, Rect<u32>),
+    /// Fills a single mip level of a single array layer.
+    FillSubResource(&'a dyn Canvas<T>, &'a [T], SubResource, Origin),
+    /// Transfers into a single mip level of a single array layer.
+    TransferSubResource {
+        f: &'a dyn Canvas<T>,
         buf: &'a [T],
         rect: Rect<i32>,
+        sub: SubResource,
+        origin: Origin,
+    },
+    /// Transfers a rect out of a larger image with rows `stride` texels
+    /// apart.
+    TransferStrided {
+        f: &'a dyn Canvas<T>,
+        buf: &'a [T],
+        stride: usize,
+        rect: Rect<i32>,
+        origin: Origin,
     },
-    Blit(
-        &'a dyn Canvas<Color = T>,
-        Rect<u32>,
-        Rect<u32>,
-    ),
 }
 
 impl<'a, T> Op<'a, T>
 where
-    T: Copy,
+    T: Texel,
 {
     fn encode(&self, dev: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         match *self {
@@ -328,6 +1127,27 @@ where
             Op::Blit(f, src, dst) => {
                 f.blit(src, dst, encoder);
             }
+            Op::FillSubResource(f, buf, sub, origin) => {
+                f.fill_subresource(buf, sub, origin, dev, encoder);
+            }
+            Op::TransferSubResource {
+                f,
+                buf,
+                rect,
+                sub,
+                origin,
+            } => {
+                f.transfer_subresource(buf, rect, sub, origin, dev, encoder);
+            }
+            Op::TransferStrided {
+                f,
+                buf,
+                stride,
+                rect,
+                origin,
+            } => {
+                f.transfer_strided(buf, stride, rect, origin, dev, encoder);
+            }
         }
     }
 }
@@ -337,21 +1157,26 @@ pub trait RenderPassExt<'a> {
         encoder: &'a mut wgpu::CommandEncoder,
         view: &'a wgpu::TextureView,
         resolve_target: Option<&'a wgpu::TextureView>,
-        depth: &'a wgpu::TextureView,
-        op: PassOp,
+        depth: DepthAttachment<'a>,
+        op: PassOp<'_>,
     ) -> Self;
 
-    fn set_easy_pipeline<'b, T>(&mut self, pipeline: &'a T)
+    fn set_easy_pipeline<T>(&mut self, pipeline: &'a T)
     where
-        T: AbstractPipeline<'b>;
+        T: Deref<Target = PipelineCore>;
+
+    /// Binds a variant's pipeline along with the shared bindings.
+    fn set_pipeline_variant(&mut self, variant: PipelineVariant<'a>);
 
     fn set_binding(&mut self, group: &'a BindingGroup, offsets: &[u32]);
 
     fn set_easy_index_buffer(&mut self, index_buf: &'a IndexBuffer);
     fn set_easy_vertex_buffer(&mut self, vertex_buf: &'a VertexBuffer);
+    fn set_easy_vertex_buffer_at(&mut self, slot: u32, vertex_buf: &'a VertexBuffer);
     fn easy_draw<T: Draw>(&mut self, drawable: &'a T, binding: &'a BindingGroup);
     fn draw_buffer(&mut self, buf: &'a VertexBuffer);
     fn draw_buffer_range(&mut self, buf: &'a VertexBuffer, range: Range<u32>);
+    fn draw_mesh(&mut self, mesh: &'a Mesh);
     fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>);
 }
 
@@ -360,53 +1185,41 @@ impl<'a> RenderPassExt<'a> for wgpu::RenderPass<'a> {
         encoder: &'a mut wgpu::CommandEncoder,
         view: &'a wgpu::TextureView,
         resolve_target: Option<&'a wgpu::TextureView>,
-        depth: &'a wgpu::TextureView,
-        op: PassOp,
+        depth: DepthAttachment<'a>,
+        op: PassOp<'_>,
     ) -> Self {
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view,
-                resolve_target,
-                ops: wgpu::Operations {
-                    load: op.to_wgpu(),
-                    store: wgpu::StoreOp::Store,
-                },
-            })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: depth,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(0),
-                    store: wgpu::StoreOp::Store,
-                }),
-            }),
-            occlusion_query_set: None,
-            timestamp_writes: None,
-        })
+        let targets = [(view, resolve_target)];
+        let loads = op.to_wgpu(targets.len());
+        begin_pass(encoder, "easygpu pass", None, None, &targets, loads, depth).into_wgpu()
     }
 
-    fn set_easy_pipeline<'b, T>(&mut self, pipeline: &'a T)
+    fn set_easy_pipeline<T>(&mut self, pipeline: &'a T)
     where
-        T: AbstractPipeline<'b>,
+        T: Deref<Target = PipelineCore>,
     {
         self.set_pipeline(&pipeline.pipeline.wgpu);
         self.set_binding(&pipeline.bindings, &[]);
     }
 
+    fn set_pipeline_variant(&mut self, variant: PipelineVariant<'a>) {
+        self.set_pipeline(variant.wgpu);
+        self.set_binding(&variant.core.bindings, &[]);
+    }
+
     fn set_binding(&mut self, group: &'a BindingGroup, offsets: &[u32]) {
         self.set_bind_group(group.set_index, &group.wgpu, offsets);
     }
 
     fn set_easy_index_buffer(&mut self, index_buf: &'a IndexBuffer) {
-        self.set_index_buffer(index_buf.slice(), wgpu::IndexFormat::Uint16)
+        self.set_index_buffer(index_buf.slice(), index_buf.format)
     }
 
     fn set_easy_vertex_buffer(&mut self, vertex_buf: &'a VertexBuffer) {
-        self.set_vertex_buffer(0, vertex_buf.slice())
+        self.set_easy_vertex_buffer_at(0, vertex_buf)
+    }
+
+    fn set_easy_vertex_buffer_at(&mut self, slot: u32, vertex_buf: &'a VertexBuffer) {
+        self.set_vertex_buffer(slot, vertex_buf.slice())
     }
 
     fn easy_draw<T: Draw>(&mut self, drawable: &'a T, binding: &'a BindingGroup) {
@@ -423,32 +1236,321 @@ impl<'a> RenderPassExt<'a> for wgpu::RenderPass<'a> {
         self.draw(range, 0..1);
     }
 
+    fn draw_mesh(&mut self, mesh: &'a Mesh) {
+        self.set_easy_vertex_buffer(&mesh.vertices);
+        self.set_easy_index_buffer(&mesh.indices);
+        self.draw_indexed(mesh.range.clone(), 0, 0..1);
+    }
+
     fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
         self.draw_indexed(indices, 0, instances)
     }
 }
 
-#[derive(Debug)]
-pub enum PassOp {
+impl<'a> RenderPassExt<'a> for Pass<'a> {
+    fn begin(
+        encoder: &'a mut wgpu::CommandEncoder,
+        view: &'a wgpu::TextureView,
+        resolve_target: Option<&'a wgpu::TextureView>,
+        depth: DepthAttachment<'a>,
+        op: PassOp<'_>,
+    ) -> Self {
+        let targets = [(view, resolve_target)];
+        let loads = op.to_wgpu(targets.len());
+        begin_pass(encoder, "easygpu pass", None, None, &targets, loads, depth)
+    }
+
+    fn set_easy_pipeline<T>(&mut self, pipeline: &'a T)
+    where
+        T: Deref<Target = PipelineCore>,
+    {
+        self.check_pipeline(&pipeline.pipeline);
+        self.pass.set_easy_pipeline(pipeline);
+    }
+
+    fn set_pipeline_variant(&mut self, variant: PipelineVariant<'a>) {
+        self.check_pipeline(&variant.core.pipeline);
+        self.pass.set_pipeline_variant(variant);
+    }
+
+    fn set_binding(&mut self, group: &'a BindingGroup, offsets: &[u32]) {
+        self.pass.set_binding(group, offsets);
+    }
+
+    fn set_easy_index_buffer(&mut self, index_buf: &'a IndexBuffer) {
+        self.pass.set_easy_index_buffer(index_buf);
+    }
+
+    fn set_easy_vertex_buffer(&mut self, vertex_buf: &'a VertexBuffer) {
+        self.pass.set_easy_vertex_buffer(vertex_buf);
+    }
+
+    fn set_easy_vertex_buffer_at(&mut self, slot: u32, vertex_buf: &'a VertexBuffer) {
+        self.pass.set_easy_vertex_buffer_at(slot, vertex_buf);
+    }
+
+    fn easy_draw<T: Draw>(&mut self, drawable: &'a T, binding: &'a BindingGroup) {
+        self.pass.easy_draw(drawable, binding);
+    }
+
+    fn draw_buffer(&mut self, buf: &'a VertexBuffer) {
+        self.pass.draw_buffer(buf);
+    }
+
+    fn draw_buffer_range(&mut self, buf: &'a VertexBuffer, range: Range<u32>) {
+        self.pass.draw_buffer_range(buf, range);
+    }
+
+    fn draw_mesh(&mut self, mesh: &'a Mesh) {
+        self.pass.draw_mesh(mesh);
+    }
+
+    fn draw_indexed(&mut self, indices: Range<u32>, instances: Range<u32>) {
+        RenderPassExt::draw_indexed(&mut self.pass, indices, instances);
+    }
+}
+
+/// Begins a render pass named `label` with one color attachment per entry of
+/// `targets`, each a view and an optional resolve target, loaded with the
+/// matching entry of `loads`. `size` is the size of the targets and `format`
+/// the format of the first, if known.
+pub(crate) fn begin_pass<'a>(
+    encoder: &'a mut wgpu::CommandEncoder,
+    label: &'static str,
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))] size: Option<Size<u32>>,
+    format: Option<wgpu::TextureFormat>,
+    targets: &[(&'a wgpu::TextureView, Option<&'a wgpu::TextureView>)],
+    loads: Vec<wgpu::LoadOp<wgpu::Color>>,
+    depth: DepthAttachment<'a>,
+) -> Pass<'a> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!(
+        "pass",
+        label,
+        targets = targets.len(),
+        ?format,
+        width = size.map(|size| size.width),
+        height = size.map(|size| size.height),
+    )
+    .entered();
+    let color_attachments = targets
+        .iter()
+        .zip(loads)
+        .map(|(&(view, resolve_target), load)| {
+            Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(label),
+        color_attachments: &color_attachments,
+        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+            view: depth.view,
+            depth_ops: depth.format.has_depth_aspect().then_some(wgpu::Operations {
+                load: depth.op.to_wgpu(),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: depth
+                .format
+                .has_stencil_aspect()
+                .then_some(wgpu::Operations {
+                    load: match depth.op {
+                        DepthOp::Clear(_) => wgpu::LoadOp::Clear(0),
+                        DepthOp::Load => wgpu::LoadOp::Load,
+                    },
+                    store: wgpu::StoreOp::Store,
+                }),
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    Pass {
+        pass,
+        format,
+        #[cfg(feature = "tracing")]
+        _span: span,
+    }
+}
+
+/// A render pass begun by a `Frame`. It derefs to the `wgpu::RenderPass`, so
+/// it can be used wherever one is expected.
+///
+/// Its `RenderPassExt` methods check that bound pipelines draw to targets of
+/// the pass's color format, panicking with a `TargetFormatError` instead of
+/// failing wgpu's validation. `Frame::pass_for` returns the error instead.
+///
+/// With the `tracing` feature, the pass's span stays entered until the pass
+/// is dropped, covering everything recorded into it.
+pub struct Pass<'a> {
+    pass: wgpu::RenderPass<'a>,
+    /// The format of the first color target, if known.
+    format: Option<wgpu::TextureFormat>,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl<'a> Pass<'a> {
+    /// Returns the underlying pass, ending its span.
+    pub fn into_wgpu(self) -> wgpu::RenderPass<'a> {
+        self.pass
+    }
+
+    /// The format of the pass's color target, if it's known.
+    pub const fn format(&self) -> Option<wgpu::TextureFormat> {
+        self.format
+    }
+
+    fn check_pipeline(&self, pipeline: &Pipeline) {
+        if let Some(format) = self.format {
+            if let Err(err) = pipeline.check_format(format) {
+                panic!("{}", err);
+            }
+        }
+    }
+}
+
+impl<'a> Deref for Pass<'a> {
+    type Target = wgpu::RenderPass<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pass
+    }
+}
+
+impl DerefMut for Pass<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.pass
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PassOp<'a> {
+    /// Clears every color target to the same color.
     Clear(Rgba),
+    /// Clears each color target to its own color, in attachment order. There
+    /// must be exactly one color per target.
+    ClearEach(&'a [Rgba]),
+    /// Keeps the existing contents of every color target.
     Load(),
 }
 
-impl PassOp {
-    fn to_wgpu(&self) -> wgpu::LoadOp<wgpu::Color> {
+impl PassOp<'_> {
+    /// Returns the load operation for each of `targets` color attachments.
+    ///
+    /// Panics if this is `ClearEach` with a different number of colors.
+    pub(crate) fn to_wgpu(self, targets: usize) -> Vec<wgpu::LoadOp<wgpu::Color>> {
+        self.to_wgpu_with(targets, |color| color)
+    }
+
+    /// Like `to_wgpu`, converting each clear color with `convert`.
+    pub(crate) fn to_wgpu_with(
+        self,
+        targets: usize,
+        convert: impl Fn(Rgba) -> Rgba,
+    ) -> Vec<wgpu::LoadOp<wgpu::Color>> {
+        match self {
+            PassOp::Clear(color) => vec![wgpu::LoadOp::Clear(convert(color).into()); targets],
+            PassOp::ClearEach(colors) => {
+                assert_eq!(
+                    colors.len(),
+                    targets,
+                    "PassOp::ClearEach has {} colors for {} color targets",
+                    colors.len(),
+                    targets
+                );
+                colors
+                    .iter()
+                    .map(|color| wgpu::LoadOp::Clear(convert(*color).into()))
+                    .collect()
+            }
+            PassOp::Load() => vec![wgpu::LoadOp::Load; targets],
+        }
+    }
+}
+
+/// The load operation for a pass's depth attachment. Stencil values are
+/// cleared to 0 or loaded to match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthOp {
+    Clear(f32),
+    Load,
+}
+
+impl DepthOp {
+    fn to_wgpu(self) -> wgpu::LoadOp<f32> {
         match self {
-            PassOp::Clear(color) => wgpu::LoadOp::Clear((*color).into()),
-            PassOp::Load() => wgpu::LoadOp::Load,
+            DepthOp::Clear(depth) => wgpu::LoadOp::Clear(depth),
+            DepthOp::Load => wgpu::LoadOp::Load,
         }
     }
 }
 
+/// The depth attachment of a render pass.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthAttachment<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    pub op: DepthOp,
+}
+
 /// Can be rendered to in a pass.
 pub trait RenderTarget {
     /// Color component.
     fn color_target(&self) -> &wgpu::TextureView;
     /// Depth component.
     fn zdepth_target(&self) -> &wgpu::TextureView;
+    /// Format of the depth component.
+    fn zdepth_format(&self) -> wgpu::TextureFormat;
+    /// Format of the color component, used to convert clear colors from the
+    /// frame's `ColorSpace`. Clear colors are used unchanged if this is
+    /// `None`, which is the default.
+    fn color_format(&self) -> Option<wgpu::TextureFormat> {
+        None
+    }
+
+    /// Size of the color component in pixels, recorded by passes' tracing
+    /// spans. `None` by default.
+    fn target_size(&self) -> Option<Size<u32>> {
+        None
+    }
+}
+
+/// A frame being rendered by `Renderer::render_offscreen`.
+pub struct OffscreenFrame<'a> {
+    frame: &'a mut Frame,
+    target: &'a Framebuffer,
+    multisample: Option<&'a wgpu::TextureView>,
+}
+
+impl OffscreenFrame<'_> {
+    /// Begins a render pass targeting the offscreen framebuffer.
+    pub fn pass(&mut self, op: PassOp<'_>) -> Pass<'_> {
+        self.frame.pass(op, self.target, self.multisample)
+    }
+
+    /// Begins a render pass like `pass`, first checking that each of
+    /// `pipelines` draws to targets of the framebuffer's format. See
+    /// `Frame::pass_for`.
+    pub fn pass_for(
+        &mut self,
+        pipelines: &[&Pipeline],
+        op: PassOp<'_>,
+    ) -> Result<Pass<'_>, TargetFormatError> {
+        self.frame
+            .pass_for(pipelines, op, self.target, self.multisample)
+    }
+
+    /// The underlying frame, for recording work outside of a render pass.
+    pub fn frame(&mut self) -> &mut Frame {
+        self.frame
+    }
 }
 
 pub struct RenderFrame {
@@ -456,6 +1558,23 @@ pub struct RenderFrame {
     pub wgpu: Option<wgpu::SurfaceTexture>,
     pub depth: DepthBuffer,
     pub size: Size<u32>,
+    clock: FrameClock,
+}
+
+impl RenderFrame {
+    /// Creates an additional view of the surface texture using `format`, which
+    /// must be one of the `SurfaceOptions::view_formats` the surface was
+    /// configured with.
+    pub fn view_as(&self, format: wgpu::TextureFormat) -> Option<TextureViewHandle> {
+        self.wgpu.as_ref().map(|surface_texture| TextureViewHandle {
+            view: surface_texture.texture.create_view(&TextureViewDescriptor {
+                format: Some(format),
+                ..TextureViewDescriptor::default()
+            }),
+            format,
+            size: self.size,
+        })
+    }
 }
 
 impl RenderTarget for RenderFrame {
@@ -466,12 +1585,38 @@ impl RenderTarget for RenderFrame {
     fn zdepth_target(&self) -> &wgpu::TextureView {
         &self.depth.texture.view
     }
+
+    fn zdepth_format(&self) -> wgpu::TextureFormat {
+        self.depth.texture.format
+    }
+
+    fn color_format(&self) -> Option<wgpu::TextureFormat> {
+        self.wgpu
+            .as_ref()
+            .map(|surface_texture| surface_texture.texture.format())
+    }
+
+    fn target_size(&self) -> Option<Size<u32>> {
+        Some(self.size)
+    }
+}
+
+impl AsRef<wgpu::TextureView> for RenderFrame {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.view
+    }
 }
 
 impl Drop for RenderFrame {
     fn drop(&mut self) {
         if let Some(wgpu) = self.wgpu.take() {
-            wgpu.present();
+            self.clock.measure(
+                || wgpu.present(),
+                |timings, elapsed| {
+                    timings.present = elapsed;
+                    timings.presented_frames += 1;
+                },
+            );
         }
     }
 }
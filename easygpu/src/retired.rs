@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wgpu::SubmissionIndex;
+
+/// Resources that are no longer needed but may still be used by work the GPU
+/// hasn't finished.
+///
+/// Retired resources wait for the next call to `submitted`, and are dropped
+/// by `collect` once the GPU has completed that submission. Completion is only
+/// detected while the device is polled; see `PollStrategy`.
+#[derive(Debug)]
+pub struct Retired<T> {
+    pending: Vec<T>,
+    in_flight: Vec<InFlight<T>>,
+}
+
+#[derive(Debug)]
+struct InFlight<T> {
+    index: SubmissionIndex,
+    done: Arc<AtomicBool>,
+    resources: Vec<T>,
+}
+
+impl<T> Default for Retired<T> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+            in_flight: Vec::new(),
+        }
+    }
+}
+
+impl<T> Retired<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps `resource` alive until the next submission completes.
+    pub fn retire(&mut self, resource: T) {
+        self.pending.push(resource);
+    }
+
+    /// Records that `index` was just submitted to `queue`, so everything
+    /// retired so far is released once it completes.
+    pub fn submitted(&mut self, queue: &wgpu::Queue, index: SubmissionIndex) {
+        if self.pending.is_empty() {
+            return;
+        }
+
+        let done = Arc::new(AtomicBool::new(false));
+        let callback_done = done.clone();
+        queue.on_submitted_work_done(move || callback_done.store(true, Ordering::Release));
+        self.in_flight.push(InFlight {
+            index,
+            done,
+            resources: std::mem::take(&mut self.pending),
+        });
+    }
+
+    /// Drops every resource whose submission has completed.
+    pub fn collect(&mut self) {
+        self.in_flight
+            .retain(|in_flight| !in_flight.done.load(Ordering::Acquire));
+    }
+
+    /// The submissions that retired resources are still waiting on, oldest
+    /// first.
+    pub fn waiting_on(&self) -> impl Iterator<Item = &SubmissionIndex> {
+        self.in_flight.iter().map(|in_flight| &in_flight.index)
+    }
+
+    /// The number of resources that haven't been dropped yet.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+            + self
+                .in_flight
+                .iter()
+                .map(|in_flight| in_flight.resources.len())
+                .sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -6,10 +6,16 @@ pub struct Sampler {
 }
 
 impl Bind for Sampler {
-    fn binding(&self, index: u32) -> wgpu::BindGroupEntry {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding: index,
             resource: wgpu::BindingResource::Sampler(&self.wgpu),
         }
     }
 }
+
+impl AsRef<wgpu::Sampler> for Sampler {
+    fn as_ref(&self) -> &wgpu::Sampler {
+        &self.wgpu
+    }
+}
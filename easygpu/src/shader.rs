@@ -4,3 +4,9 @@ pub struct Shader {
 }
 
 pub use wgpu::ShaderStages;
+
+impl AsRef<wgpu::ShaderModule> for Shader {
+    fn as_ref(&self) -> &wgpu::ShaderModule {
+        &self.wgpu
+    }
+}
@@ -0,0 +1,83 @@
+// Image filters applied by `Renderer::filter`. `STORAGE_FORMAT` is replaced
+// with the format of the destination texture.
+
+struct Params {
+    // 0 convolves along `direction`, 1 detects edges, 2 thresholds.
+    mode: u32,
+    radius: i32,
+    // The standard deviation of a gaussian blur, or 0 for a box blur.
+    sigma: f32,
+    threshold: f32,
+    direction: vec2<i32>,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var src: texture_2d<f32>;
+@group(0) @binding(2) var dst: texture_storage_2d<STORAGE_FORMAT, write>;
+
+// Texels outside the source repeat its edges.
+fn load(coords: vec2<i32>) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(src));
+    return textureLoad(src, clamp(coords, vec2<i32>(0), size - 1), 0);
+}
+
+fn luminance(color: vec4<f32>) -> f32 {
+    return dot(color.rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+fn convolve(coords: vec2<i32>) -> vec4<f32> {
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = -params.radius; i <= params.radius; i++) {
+        var weight = 1.0;
+        if params.sigma > 0.0 {
+            let x = f32(i) / params.sigma;
+            weight = exp(-0.5 * x * x);
+        }
+        sum += load(coords + params.direction * i) * weight;
+        total += weight;
+    }
+    return sum / total;
+}
+
+fn sobel(coords: vec2<i32>) -> vec4<f32> {
+    var gradient = vec2<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let l = luminance(load(coords + vec2<i32>(x, y)));
+            // The center row and column of each kernel are weighted twice.
+            gradient += vec2<f32>(
+                f32(x) * f32(2 - abs(y)),
+                f32(y) * f32(2 - abs(x)),
+            ) * l;
+        }
+    }
+    return vec4<f32>(vec3<f32>(min(length(gradient), 1.0)), load(coords).a);
+}
+
+fn threshold(coords: vec2<i32>) -> vec4<f32> {
+    let color = load(coords);
+    return vec4<f32>(vec3<f32>(step(params.threshold, luminance(color))), color.a);
+}
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= textureDimensions(dst)) {
+        return;
+    }
+
+    let coords = vec2<i32>(id.xy);
+    var color: vec4<f32>;
+    switch params.mode {
+        case 0u: {
+            color = convolve(coords);
+        }
+        case 1u: {
+            color = sobel(coords);
+        }
+        default: {
+            color = threshold(coords);
+        }
+    }
+    textureStore(dst, coords, color);
+}
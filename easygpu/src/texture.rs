@@ -1,11 +1,14 @@
+use std::ops::Deref;
+
 use figures::{Point, Rect, Size};
 use wgpu::TextureAspect;
 
 use crate::binding::Bind;
-use crate::buffers::Framebuffer;
-use crate::canvas::Canvas;
-use crate::color::Rgba8;
+use crate::buffers::{DepthBuffer, Framebuffer};
+use crate::canvas::{encode_texels, Canvas, Texel};
 use crate::device::Device;
+use crate::error::ResourceError;
+use crate::renderer::RenderTarget;
 
 #[derive(Debug)]
 pub struct Texture {
@@ -17,119 +20,511 @@ pub struct Texture {
     pub size: Size<u32>,
 }
 
+/// A single mip level of a single array layer of a `Texture`, the destination
+/// of `Texture::fill_subresource` and `Texture::transfer_subresource`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SubResource {
+    pub mip: u32,
+    pub layer: u32,
+}
+
+impl SubResource {
+    pub const fn new(mip: u32, layer: u32) -> Self {
+        Self { mip, layer }
+    }
+}
+
+/// Where the coordinates of uploaded rects are measured from, and the order in
+/// which rows of texels are given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Origin {
+    /// Y grows downwards and the first row is the top one, as in wgpu and
+    /// most image formats.
+    #[default]
+    TopLeft,
+    /// Y grows upwards and the first row is the bottom one, as in OpenGL.
+    BottomLeft,
+}
+
+impl Origin {
+    /// Converts `rect`, measured from this origin in a texture `height` texels
+    /// tall, to wgpu's top-left coordinates.
+    pub const fn to_top_left(self, rect: Rect<u32>, height: u32) -> Rect<u32> {
+        match self {
+            Origin::TopLeft => rect,
+            Origin::BottomLeft => Rect::new(
+                Point::new(rect.origin.x, height - rect.origin.y - rect.size.height),
+                rect.size,
+            ),
+        }
+    }
+}
+
+/// Selects the subresources of a `Texture` covered by a view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewOptions {
+    pub base_mip: u32,
+    /// The number of mip levels, or `None` for every level from `base_mip`.
+    pub mip_count: Option<u32>,
+    pub base_layer: u32,
+    /// The number of array layers, or `None` for every layer from
+    /// `base_layer`.
+    pub layer_count: Option<u32>,
+    pub aspect: TextureAspect,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        Self {
+            base_mip: 0,
+            mip_count: None,
+            base_layer: 0,
+            layer_count: None,
+            aspect: TextureAspect::All,
+        }
+    }
+}
+
+impl ViewOptions {
+    /// A view of a single mip level of a single layer.
+    pub fn mip(level: u32) -> Self {
+        Self {
+            base_mip: level,
+            mip_count: Some(1),
+            layer_count: Some(1),
+            ..Self::default()
+        }
+    }
+
+    /// A view of every mip level of a single array layer.
+    pub fn layer(layer: u32) -> Self {
+        Self {
+            base_layer: layer,
+            layer_count: Some(1),
+            ..Self::default()
+        }
+    }
+}
+
+/// A view of part of a `Texture`, created by `Texture::create_view`.
+///
+/// It can be bound like a texture, used wherever a `wgpu::TextureView` is
+/// expected, or paired with a depth buffer using `with_depth` to render into.
+#[derive(Debug)]
+pub struct TextureViewHandle {
+    pub view: wgpu::TextureView,
+    /// The format the view's texels are read and written as.
+    pub format: wgpu::TextureFormat,
+    /// The size of the view's base mip level.
+    pub size: Size<u32>,
+}
+
+impl TextureViewHandle {
+    pub fn with_depth<'a>(&'a self, depth: &'a DepthBuffer) -> ViewTarget<'a> {
+        ViewTarget {
+            color: &self.view,
+            depth,
+            format: self.format,
+            size: self.size,
+        }
+    }
+}
+
+impl Deref for TextureViewHandle {
+    type Target = wgpu::TextureView;
+
+    fn deref(&self) -> &Self::Target {
+        &self.view
+    }
+}
+
+impl AsRef<wgpu::TextureView> for TextureViewHandle {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+impl Bind for TextureViewHandle {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
+        wgpu::BindGroupEntry {
+            binding: index,
+            resource: wgpu::BindingResource::TextureView(&self.view),
+        }
+    }
+}
+
+/// A color view and depth view that can be rendered to in a pass.
+#[derive(Debug, Clone, Copy)]
+pub struct ViewTarget<'a> {
+    pub color: &'a wgpu::TextureView,
+    pub depth: &'a DepthBuffer,
+    /// The format of `color`.
+    pub format: wgpu::TextureFormat,
+    /// The size of `color` in pixels.
+    pub size: Size<u32>,
+}
+
+impl RenderTarget for ViewTarget<'_> {
+    fn color_target(&self) -> &wgpu::TextureView {
+        self.color
+    }
+
+    fn zdepth_target(&self) -> &wgpu::TextureView {
+        &self.depth.texture.view
+    }
+
+    fn zdepth_format(&self) -> wgpu::TextureFormat {
+        self.depth.texture.format
+    }
+
+    fn color_format(&self) -> Option<wgpu::TextureFormat> {
+        Some(self.format)
+    }
+
+    fn target_size(&self) -> Option<Size<u32>> {
+        Some(self.size)
+    }
+}
+
 impl Texture {
-    pub fn clear<T>(
+    /// Wraps a texture created outside of easygpu, such as by a video decoder.
+    ///
+    /// `format` and `size` must match the texture, which must have been
+    /// created with `TEXTURE_BINDING` usage.
+    pub fn from_wgpu(
+        texture: wgpu::Texture,
+        format: wgpu::TextureFormat,
+        size: Size<u32>,
+    ) -> Result<Self, ResourceError> {
+        let required = wgpu::TextureUsages::TEXTURE_BINDING;
+        if !texture.usage().contains(required) {
+            return Err(ResourceError::MissingTextureUsage {
+                required,
+                actual: texture.usage(),
+            });
+        }
+        if texture.format() != format {
+            return Err(ResourceError::FormatMismatch {
+                expected: format,
+                actual: texture.format(),
+            });
+        }
+        if (texture.width(), texture.height()) != (size.width, size.height) {
+            return Err(ResourceError::SizeMismatch {
+                expected: (size.width, size.height),
+                actual: (texture.width(), texture.height()),
+            });
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Ok(Self {
+            extent: texture.size(),
+            wgpu: texture,
+            view,
+            format,
+            size,
+        })
+    }
+
+    /// The number of array layers in this texture.
+    pub fn layers(&self) -> u32 {
+        self.extent.depth_or_array_layers
+    }
+
+    /// The number of mip levels in this texture.
+    pub fn mip_level_count(&self) -> u32 {
+        self.wgpu.mip_level_count()
+    }
+
+    /// Creates a view of a subset of this texture's mip levels and layers.
+    pub fn create_view(&self, options: ViewOptions) -> TextureViewHandle {
+        let layer_count = options
+            .layer_count
+            .unwrap_or_else(|| self.layers().saturating_sub(options.base_layer));
+        let dimension = if layer_count == 1 {
+            wgpu::TextureViewDimension::D2
+        } else {
+            wgpu::TextureViewDimension::D2Array
+        };
+        let view = self.wgpu.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(dimension),
+            aspect: options.aspect,
+            base_mip_level: options.base_mip,
+            mip_level_count: options.mip_count,
+            base_array_layer: options.base_layer,
+            array_layer_count: Some(layer_count),
+            ..wgpu::TextureViewDescriptor::default()
+        });
+        TextureViewHandle {
+            view,
+            format: self
+                .format
+                .aspect_specific_format(options.aspect)
+                .unwrap_or(self.format),
+            size: self.mip_size(options.base_mip),
+        }
+    }
+
+    /// Creates an additional view of this texture using `format`, which must
+    /// be one of the view formats the texture was created with.
+    pub fn view_as(&self, format: wgpu::TextureFormat) -> TextureViewHandle {
+        let view = self.wgpu.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(format),
+            ..wgpu::TextureViewDescriptor::default()
+        });
+        TextureViewHandle {
+            view,
+            format,
+            size: self.size,
+        }
+    }
+
+    pub fn clear<T: Texel>(
         texture: &Texture,
         value: T,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
-    ) where
-        T: Clone,
-    {
-        let capacity = texture.size.area() as usize;
-        let mut texels: Vec<T> = Vec::with_capacity(capacity);
-        texels.resize(capacity, value);
+    ) {
+        let texels = vec![value; texture.size.area() as usize];
+        Self::fill(texture, &texels, device, encoder);
+    }
 
-        let (head, body, tail) = unsafe { texels.align_to::<Rgba8>() };
-        assert!(head.is_empty());
-        assert!(tail.is_empty());
+    pub fn fill<T: Texel>(
+        texture: &Texture,
+        texels: &[T],
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Self::fill_subresource(
+            texture,
+            texels,
+            SubResource::default(),
+            Origin::TopLeft,
+            device,
+            encoder,
+        );
+    }
 
-        Self::fill(texture, body, device, encoder);
+    /// Fills mip level `sub.mip` of array layer `sub.layer` with `texels`,
+    /// which must cover the whole level. Rows are ordered according to
+    /// `origin`.
+    pub fn fill_subresource<T: Texel>(
+        texture: &Texture,
+        texels: &[T],
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        let size = texture.mip_size(sub.mip);
+        Self::upload(
+            texture,
+            texels,
+            size.width as usize,
+            Rect::new(Point::default(), size),
+            sub,
+            origin,
+            device,
+            encoder,
+        );
     }
 
-    pub fn fill<T: bytemuck::Pod + 'static>(
+    /// The size of mip level `mip`.
+    pub fn mip_size(&self, mip: u32) -> Size<u32> {
+        Size::new(
+            (self.size.width >> mip).max(1),
+            (self.size.height >> mip).max(1),
+        )
+    }
+
+    fn assert_subresource(&self, sub: SubResource) {
+        assert!(
+            sub.mip < self.mip_level_count(),
+            "mip level {} is out of range for a texture with {} levels",
+            sub.mip,
+            self.mip_level_count()
+        );
+        assert!(
+            sub.layer < self.layers(),
+            "layer {} is out of range for a texture with {} layers",
+            sub.layer,
+            self.layers()
+        );
+    }
+
+    /// Copies `texels` into `rect`, with the rows given top row first and
+    /// `rect` measured from the top left of the texture, as with
+    /// `Origin::TopLeft`. See `transfer_with_origin` for bottom-up images.
+    pub fn transfer<T>(
         texture: &Texture,
         texels: &[T],
+        rect: Rect<i32>,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
     ) where
-        T: Clone + Copy,
+        T: Texel,
     {
-        assert!(
-            texels.len() as u32 >= texture.size.area(),
-            "fatal: incorrect length for texel buffer"
-        );
+        Self::transfer_with_origin(texture, texels, rect, Origin::TopLeft, device, encoder);
+    }
 
-        let buf = device.create_buffer_from_slice(texels, wgpu::BufferUsages::COPY_SRC);
+    /// Copies `texels` into `rect`, measuring `rect` from `origin` and taking
+    /// rows in its order.
+    pub fn transfer_with_origin<T>(
+        texture: &Texture,
+        texels: &[T],
+        rect: Rect<i32>,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) where
+        T: Texel,
+    {
+        Self::transfer_subresource(
+            texture,
+            texels,
+            rect,
+            SubResource::default(),
+            origin,
+            device,
+            encoder,
+        );
+    }
 
-        Self::copy(
-            &texture.wgpu,
-            Rect::new(Point::default(), texture.size),
-            texels.len() as u32 / texture.extent.height * 4,
-            texture.extent,
-            &buf,
+    /// Copies `texels` into `rect` of mip level `sub.mip` of array layer
+    /// `sub.layer`. `rect` is in the coordinates of that mip level, measured
+    /// from `origin`, and may have a negative size. It must lie within the
+    /// level.
+    pub fn transfer_subresource<T>(
+        texture: &Texture,
+        texels: &[T],
+        rect: Rect<i32>,
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) where
+        T: Texel,
+    {
+        let rect = Self::normalize(rect);
+        Self::upload(
+            texture,
+            texels,
+            rect.size.width as usize,
+            rect,
+            sub,
+            origin,
+            device,
             encoder,
         );
     }
 
-    pub fn transfer<T: bytemuck::Pod + 'static>(
+    /// Copies a `rect` of texels out of a larger image whose rows are
+    /// `stride` texels apart, without packing them first. `texels` starts at
+    /// the first texel of the rect, so uploading the region at `x, y` of a
+    /// top-down image `stride` texels wide uses
+    /// `&image[y * stride + x..]` with `Origin::TopLeft`.
+    pub fn transfer_strided<T>(
         texture: &Texture,
         texels: &[T],
+        stride: usize,
         rect: Rect<i32>,
+        origin: Origin,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
     ) where
-        T: Into<Rgba8> + Clone + Copy,
+        T: Texel,
     {
-        // Wgpu's coordinate system has a downwards pointing Y axis.
-        let destination = rect;
-        // Make sure we have a positive rectangle
-        let destination = Rect::from_extents(
-            Point::new(
-            destination.origin.x.min(destination.extent().x),
-            destination.origin.y.min(destination.extent().y),
-            ),
-            Point::new(
-                destination.extent().x.max(destination.origin.x) - destination.origin.x.min(destination.extent().x),
-                destination.extent().y.max(destination.origin.y) - destination.origin.y.min(destination.extent().y),
-            )
+        let rect = Self::normalize(rect);
+        assert!(
+            stride >= rect.size.width as usize,
+            "fatal: stride {stride} is less than the rect width {}",
+            rect.size.width
         );
-        // flip y, making it negative in the y direction
-        let rect = Rect::from_extents(
-            Point::new(destination.origin.x, destination.extent().y),
-            Point::new(destination.extent().x, destination.origin.y),
+        Self::upload(
+            texture,
+            texels,
+            stride,
+            rect,
+            SubResource::default(),
+            origin,
+            device,
+            encoder,
         );
+    }
 
-        // The width and height of the transfer area.
-        let destination_size = Size::new(rect.size.width as u32, rect.size.height as u32);
-
-        // The destination coordinate of the transfer, on the texture.
-        // We have to invert the Y coordinate as explained above.
-        let destination_point = Point::new(
-            rect.origin.x as u32,
-            texture.size.height - rect.origin.y as u32,
+    /// Gives `rect` a positive size, panicking if it starts at negative
+    /// coordinates.
+    fn normalize(rect: Rect<i32>) -> Rect<u32> {
+        let rect = Rect::from_extents(rect.origin, rect.extent());
+        assert!(
+            rect.origin.x >= 0 && rect.origin.y >= 0,
+            "fatal: transfer rect {rect:?} starts outside of the texture"
         );
+        Rect::new(
+            Point::new(rect.origin.x as u32, rect.origin.y as u32),
+            Size::new(rect.size.width as u32, rect.size.height as u32),
+        )
+    }
 
+    /// Copies the texels of `rect`, measured from `origin`, from rows that
+    /// are `stride` texels apart and ordered from `origin`.
+    #[allow(clippy::too_many_arguments)]
+    fn upload<T: Texel>(
+        texture: &Texture,
+        texels: &[T],
+        stride: usize,
+        rect: Rect<u32>,
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Self::assert_texel::<T>(texture);
+        texture.assert_subresource(sub);
+        let size = texture.mip_size(sub.mip);
+        let extent = rect.extent();
         assert!(
-            destination_size.area() <= texture.size.area(),
-            "fatal: transfer size must be <= texture size"
+            extent.x <= size.width && extent.y <= size.height,
+            "fatal: rect {rect:?} exceeds the {size:?} texture"
+        );
+        if rect.size.area() == 0 {
+            return;
+        }
+        let (width, rows) = (rect.size.width as usize, rect.size.height as usize);
+        let len = stride * (rows - 1) + width;
+        assert!(
+            texels.len() >= len,
+            "fatal: incorrect length for texel buffer"
         );
 
-        let buf = device.create_buffer_from_slice(texels, wgpu::BufferUsages::COPY_SRC);
+        let texels = encode_texels(&texels[..len], device.color_space());
+        let (buf, bytes_per_row) = Self::staging_buffer(
+            device,
+            &texels,
+            width,
+            stride,
+            rows,
+            origin == Origin::BottomLeft,
+        );
 
-        let extent = wgpu::Extent3d {
-            width: destination_size.width,
-            height: destination_size.height,
-            depth_or_array_layers: 1,
-        };
         Self::copy(
             &texture.wgpu,
-            Rect::new(destination_point, destination_size.cast()),
-            texels.len() as u32 / destination_size.height * 4,
-            extent,
+            sub,
+            origin.to_top_left(rect, size.height),
+            bytes_per_row,
+            wgpu::Extent3d {
+                width: rect.size.width,
+                height: rect.size.height,
+                depth_or_array_layers: 1,
+            },
             &buf,
             encoder,
         );
     }
 
-    fn blit(
-        &self,
-        src: Rect<u32>,
-        dst: Rect<u32>,
-        encoder: &mut wgpu::CommandEncoder,
-    ) {
+    fn blit(&self, src: Rect<u32>, dst: Rect<u32>, encoder: &mut wgpu::CommandEncoder) {
         assert!(
-            src.size.area() != dst.size.area(),
+            src.size == dst.size,
             "source and destination rectangles must be of the same size"
         );
 
@@ -162,8 +557,78 @@ impl Texture {
         );
     }
 
+    fn assert_texel<T: Texel>(texture: &Texture) {
+        assert!(
+            T::matches(texture.format),
+            "{} texels cannot be copied into a {:?} texture",
+            std::any::type_name::<T>(),
+            texture.format
+        );
+    }
+
+    /// Creates a buffer holding `rows` rows of `row_len` texels, taken
+    /// `stride` texels apart from `texels`, padding each row to
+    /// `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT` as texture copies require. If
+    /// `flip_y` is set, the rows are stored in reverse order. Returns the
+    /// buffer and its bytes per row.
+    fn staging_buffer<T: Texel>(
+        device: &Device,
+        texels: &[T],
+        row_len: usize,
+        stride: usize,
+        rows: usize,
+        flip_y: bool,
+    ) -> (wgpu::Buffer, u32) {
+        let bytes: &[u8] = bytemuck::cast_slice(texels);
+        let row_bytes = row_len * std::mem::size_of::<T>();
+        let stride_bytes = stride * std::mem::size_of::<T>();
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+
+        // Rows that are already aligned are copied straight from the source.
+        if stride_bytes.is_multiple_of(alignment) && !flip_y {
+            let len = stride_bytes * (rows - 1) + row_bytes;
+            let buf = device.create_buffer_from_slice(&bytes[..len], wgpu::BufferUsages::COPY_SRC);
+            return (buf, stride_bytes as u32);
+        }
+
+        let (padded, padded_row_bytes) =
+            Self::pad_rows(bytes, row_bytes, stride_bytes, rows, flip_y);
+        let buf = device.create_buffer_from_slice(&padded, wgpu::BufferUsages::COPY_SRC);
+
+        (buf, padded_row_bytes as u32)
+    }
+
+    /// Copies `rows` rows of `row_bytes` bytes, taken `stride_bytes` apart
+    /// from `bytes`, into rows padded to `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`,
+    /// in reverse order if `flip_y` is set. Returns the padded rows and their
+    /// size.
+    fn pad_rows(
+        bytes: &[u8],
+        row_bytes: usize,
+        stride_bytes: usize,
+        rows: usize,
+        flip_y: bool,
+    ) -> (Vec<u8>, usize) {
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let padded_row_bytes = row_bytes.div_ceil(alignment) * alignment;
+        let mut padded = vec![0u8; padded_row_bytes * rows];
+        let rows = (0..rows).map(|row| &bytes[row * stride_bytes..][..row_bytes]);
+        let padded_rows = padded.chunks_exact_mut(padded_row_bytes);
+        let padded_rows: Box<dyn Iterator<Item = &mut [u8]>> = if flip_y {
+            Box::new(padded_rows.rev())
+        } else {
+            Box::new(padded_rows)
+        };
+        for (padded_row, row) in padded_rows.zip(rows) {
+            padded_row[..row_bytes].copy_from_slice(row);
+        }
+
+        (padded, padded_row_bytes)
+    }
+
     fn copy(
         texture: &wgpu::Texture,
+        sub: SubResource,
         destination: Rect<u32>,
         bytes_per_row: u32,
         extent: wgpu::Extent3d,
@@ -181,11 +646,11 @@ impl Texture {
             },
             wgpu::TexelCopyTextureInfo {
                 texture,
-                mip_level: 0,
+                mip_level: sub.mip,
                 origin: wgpu::Origin3d {
                     x: destination.origin.x,
                     y: destination.origin.y,
-                    z: 0,
+                    z: sub.layer,
                 },
                 aspect: TextureAspect::All,
             },
@@ -194,8 +659,20 @@ impl Texture {
     }
 }
 
+impl AsRef<wgpu::Texture> for Texture {
+    fn as_ref(&self) -> &wgpu::Texture {
+        &self.wgpu
+    }
+}
+
+impl AsRef<wgpu::TextureView> for Texture {
+    fn as_ref(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
 impl Bind for Texture {
-    fn binding(&self, index: u32) -> wgpu::BindGroupEntry {
+    fn binding(&self, index: u32) -> wgpu::BindGroupEntry<'_> {
         wgpu::BindGroupEntry {
             binding: index,
             resource: wgpu::BindingResource::TextureView(&self.view),
@@ -203,20 +680,18 @@ impl Bind for Texture {
     }
 }
 
-impl Canvas for Texture {
-    type Color = Rgba8;
-
-    fn fill(&self, buf: &[Rgba8], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+impl<T: Texel> Canvas<T> for Texture {
+    fn fill(&self, buf: &[T], device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::fill(self, buf, device, encoder);
     }
 
-    fn clear(&self, color: Rgba8, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
+    fn clear(&self, color: T, device: &mut Device, encoder: &mut wgpu::CommandEncoder) {
         Texture::clear(self, color, device, encoder);
     }
 
     fn transfer(
         &self,
-        buf: &[Rgba8],
+        buf: &[T],
         rect: Rect<i32>,
         device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
@@ -224,12 +699,42 @@ impl Canvas for Texture {
         Texture::transfer(self, buf, rect, device, encoder);
     }
 
-    fn blit(
+    fn fill_subresource(
+        &self,
+        buf: &[T],
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Texture::fill_subresource(self, buf, sub, origin, device, encoder);
+    }
+
+    fn transfer_subresource(
+        &self,
+        buf: &[T],
+        rect: Rect<i32>,
+        sub: SubResource,
+        origin: Origin,
+        device: &mut Device,
+        encoder: &mut wgpu::CommandEncoder,
+    ) {
+        Texture::transfer_subresource(self, buf, rect, sub, origin, device, encoder);
+    }
+
+    fn transfer_strided(
         &self,
-        src: Rect<u32>,
-        dst: Rect<u32>,
+        buf: &[T],
+        stride: usize,
+        rect: Rect<i32>,
+        origin: Origin,
+        device: &mut Device,
         encoder: &mut wgpu::CommandEncoder,
     ) {
+        Texture::transfer_strided(self, buf, stride, rect, origin, device, encoder);
+    }
+
+    fn blit(&self, src: Rect<u32>, dst: Rect<u32>, encoder: &mut wgpu::CommandEncoder) {
         Texture::blit(self, src, dst, encoder);
     }
 }
@@ -239,3 +744,127 @@ impl From<Framebuffer> for Texture {
         fb.texture
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIGNMENT: usize = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> Rect<u32> {
+        Rect::new(Point::new(x, y), Size::new(width, height))
+    }
+
+    #[test]
+    fn top_left_rects_are_unchanged() {
+        assert_eq!(
+            Origin::TopLeft.to_top_left(rect(1, 2, 3, 4), 8),
+            rect(1, 2, 3, 4)
+        );
+    }
+
+    #[test]
+    fn bottom_left_rects_are_flipped() {
+        // Touching the bottom row.
+        assert_eq!(
+            Origin::BottomLeft.to_top_left(rect(2, 0, 3, 1), 8),
+            rect(2, 7, 3, 1)
+        );
+        // Touching the top row.
+        assert_eq!(
+            Origin::BottomLeft.to_top_left(rect(0, 6, 4, 2), 8),
+            rect(0, 0, 4, 2)
+        );
+        assert_eq!(
+            Origin::BottomLeft.to_top_left(rect(1, 2, 3, 4), 8),
+            rect(1, 2, 3, 4)
+        );
+    }
+
+    #[test]
+    fn full_rects_cover_the_texture_from_either_origin() {
+        for origin in [Origin::TopLeft, Origin::BottomLeft] {
+            assert_eq!(origin.to_top_left(rect(0, 0, 5, 8), 8), rect(0, 0, 5, 8));
+        }
+    }
+
+    #[test]
+    fn zero_height_rects_stay_in_bounds() {
+        assert_eq!(
+            Origin::BottomLeft.to_top_left(rect(0, 0, 4, 0), 8),
+            rect(0, 8, 4, 0)
+        );
+        assert_eq!(
+            Origin::BottomLeft.to_top_left(rect(0, 8, 4, 0), 8),
+            rect(0, 0, 4, 0)
+        );
+    }
+
+    #[test]
+    fn normalize_makes_sizes_positive() {
+        let normalized = |x, y, width, height| {
+            Texture::normalize(Rect::new(Point::new(x, y), Size::new(width, height)))
+        };
+        assert_eq!(normalized(0, 0, 16, 8), rect(0, 0, 16, 8));
+        assert_eq!(normalized(4, 6, -4, -2), rect(0, 4, 4, 2));
+        assert_eq!(normalized(3, 5, 2, 0), rect(3, 5, 2, 0));
+        assert_eq!(normalized(3, 5, -3, 0), rect(0, 5, 3, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "starts outside of the texture")]
+    fn normalize_rejects_negative_origins() {
+        Texture::normalize(Rect::new(Point::new(2, 1), Size::new(-3, 1)));
+    }
+
+    /// Rows of `row_bytes` bytes holding the row index, `stride_bytes` apart.
+    fn numbered_rows(rows: usize, row_bytes: usize, stride_bytes: usize) -> Vec<u8> {
+        (0..rows * stride_bytes)
+            .map(|byte| {
+                if byte % stride_bytes < row_bytes {
+                    (byte / stride_bytes) as u8
+                } else {
+                    0xFF
+                }
+            })
+            .collect()
+    }
+
+    fn padded_row_values(padded: &[u8], padded_row_bytes: usize, row_bytes: usize) -> Vec<u8> {
+        padded
+            .chunks_exact(padded_row_bytes)
+            .map(|row| {
+                assert!(row[..row_bytes].iter().all(|&byte| byte == row[0]));
+                assert!(row[row_bytes..].iter().all(|&byte| byte == 0));
+                row[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn staged_rows_keep_their_order() {
+        let bytes = numbered_rows(3, 12, 20);
+        let (padded, padded_row_bytes) = Texture::pad_rows(&bytes, 12, 20, 3, false);
+        assert_eq!(padded_row_bytes, ALIGNMENT);
+        assert_eq!(padded_row_values(&padded, padded_row_bytes, 12), [0, 1, 2]);
+    }
+
+    #[test]
+    fn bottom_left_rows_are_staged_in_reverse() {
+        let bytes = numbered_rows(3, 12, 20);
+        let (padded, padded_row_bytes) = Texture::pad_rows(&bytes, 12, 20, 3, true);
+        assert_eq!(padded_row_values(&padded, padded_row_bytes, 12), [2, 1, 0]);
+    }
+
+    #[test]
+    fn wide_rows_are_padded_to_the_next_alignment() {
+        let row_bytes = ALIGNMENT + 4;
+        let bytes = numbered_rows(2, row_bytes, row_bytes);
+        let (padded, padded_row_bytes) = Texture::pad_rows(&bytes, row_bytes, row_bytes, 2, true);
+        assert_eq!(padded_row_bytes, 2 * ALIGNMENT);
+        assert_eq!(
+            padded_row_values(&padded, padded_row_bytes, row_bytes),
+            [1, 0]
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// CPU-side timings of the most recent frame, returned by
+/// `Renderer::frame_timings`.
+///
+/// A long `acquire` or `present` means the renderer is being throttled by the
+/// swapchain, such as by vsync. A long `encode` means the time is being spent
+/// building the frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTimings {
+    /// Time spent in `Renderer::current_frame` waiting for a surface texture.
+    pub acquire: Duration,
+    /// Time spent in `Renderer::frame` waiting for earlier frames to complete,
+    /// when the renderer limits its frames in flight. See
+    /// `Renderer::set_max_frames_in_flight`.
+    pub throttle: Duration,
+    /// Time from `Renderer::frame` until the frame was submitted by
+    /// `Renderer::present` or `Renderer::present_all`.
+    pub encode: Duration,
+    /// Time spent presenting the surface texture when its `RenderFrame` was
+    /// dropped.
+    pub present: Duration,
+    /// The number of surface textures presented since the renderer was
+    /// created.
+    pub presented_frames: u64,
+}
+
+/// Shared between a `Renderer` and the `RenderFrame`s it hands out, which
+/// present themselves when dropped.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FrameClock(Arc<Mutex<ClockState>>);
+
+#[derive(Debug, Default)]
+struct ClockState {
+    timings: FrameTimings,
+    /// When the oldest frame that hasn't been presented yet was created.
+    encode_start: Option<Instant>,
+}
+
+impl FrameClock {
+    pub fn timings(&self) -> FrameTimings {
+        self.lock().timings
+    }
+
+    /// Runs `f`, recording its duration with `record`.
+    pub fn measure<R>(
+        &self,
+        f: impl FnOnce() -> R,
+        record: impl FnOnce(&mut FrameTimings, Duration),
+    ) -> R {
+        let start = Instant::now();
+        let result = f();
+        record(&mut self.lock().timings, start.elapsed());
+        result
+    }
+
+    pub fn start_encode(&self) {
+        self.lock().encode_start.get_or_insert_with(Instant::now);
+    }
+
+    pub fn finish_encode(&self) {
+        let mut state = self.lock();
+        if let Some(started) = state.encode_start.take() {
+            state.timings.encode = started.elapsed();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        // The timings are always left consistent, so a panic while holding
+        // the lock doesn't invalidate them.
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -1,6 +1,7 @@
-use std::ops::{Add, Div, Neg, Sub};
+use std::ops::{Add, Div, Mul, Neg, Sub};
 
-use num_traits::{One, Zero};
+use figures::Size;
+use num_traits::{Float, One, Zero};
 
 #[derive(Clone, Copy, Debug)]
 pub struct ScreenTransformation<S>([S; 16]);
@@ -49,6 +50,85 @@ where
     }
 }
 
+impl<S: Float> ScreenTransformation<S> {
+    /// A right-handed perspective projection for use with
+    /// `DepthMode::ReverseZ`, mapping `near` to a depth of 1 and `far` to a
+    /// depth of 0. `fov_y` is in radians.
+    pub fn perspective_reverse_z(fov_y: S, aspect_ratio: S, near: S, far: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        let two = one + one;
+        let focal_length = one / (fov_y / two).tan();
+        Self([
+            // Row one
+            focal_length / aspect_ratio,
+            zero,
+            zero,
+            zero,
+            // Row two
+            zero,
+            focal_length,
+            zero,
+            zero,
+            // Row three
+            zero,
+            zero,
+            near / (far - near),
+            -one,
+            // Row four
+            zero,
+            zero,
+            far * near / (far - near),
+            zero,
+        ])
+    }
+}
+
+/// Where `ScreenTransformation::pixel_perfect_aligned` places integer
+/// coordinates within a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelAlignment {
+    /// Integer coordinates land on the edges between pixels. Shapes whose
+    /// edges are on integer coordinates, such as filled rectangles, cover
+    /// whole pixels.
+    Edges,
+    /// Integer coordinates land on pixel centers, offsetting everything by
+    /// half a pixel. Strokes with an odd width centered on integer
+    /// coordinates, such as 1px lines, cover whole pixels instead of half of
+    /// two pixels each.
+    Centers,
+}
+
+impl ScreenTransformation<f32> {
+    /// An orthographic projection from pixel coordinates, with the origin in
+    /// the top-left corner and Y pointing down.
+    ///
+    /// Without multisampling, a 1px stroke centered on a pixel edge still
+    /// covers exactly one pixel, so integer coordinates are aligned with pixel
+    /// edges. With multisampling, the same stroke covers half of two pixels
+    /// and the MSAA resolve blurs it across both, so integer coordinates are
+    /// aligned with pixel centers instead. Use `pixel_perfect_aligned` to
+    /// choose the alignment explicitly.
+    pub fn pixel_perfect(size: Size<u32>, sample_count: u32) -> Self {
+        let alignment = if sample_count > 1 {
+            PixelAlignment::Centers
+        } else {
+            PixelAlignment::Edges
+        };
+        Self::pixel_perfect_aligned(size, alignment)
+    }
+
+    /// An orthographic projection from pixel coordinates like
+    /// `pixel_perfect`, with an explicit `alignment`.
+    pub fn pixel_perfect_aligned(size: Size<u32>, alignment: PixelAlignment) -> Self {
+        let ortho = Self::ortho(0., 0., size.width as f32, size.height as f32, -1., 1.);
+        match alignment {
+            PixelAlignment::Edges => ortho,
+            PixelAlignment::Centers => ortho * Self::translation(0.5, 0.5, 0.),
+        }
+    }
+}
+
 impl<S> ScreenTransformation<S>
 where
     S: One + Zero + Copy,
@@ -66,9 +146,258 @@ where
     }
 }
 
+impl<S> ScreenTransformation<S>
+where
+    S: One + Zero + Copy,
+{
+    #[rustfmt::skip]
+    pub fn translation(x: S, y: S, z: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        Self([
+            one , zero, zero, zero,
+            zero, one , zero, zero,
+            zero, zero, one , zero,
+            x   , y   , z   , one ,
+        ])
+    }
+
+    #[rustfmt::skip]
+    pub fn scale(x: S, y: S, z: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        Self([
+            x   , zero, zero, zero,
+            zero, y   , zero, zero,
+            zero, zero, z   , zero,
+            zero, zero, zero, one ,
+        ])
+    }
+}
+
+impl<S: Float> ScreenTransformation<S> {
+    /// A counter-clockwise rotation around the X axis. `angle` is in radians.
+    #[rustfmt::skip]
+    pub fn rotation_x(angle: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        let (sin, cos) = angle.sin_cos();
+        Self([
+            one , zero, zero, zero,
+            zero, cos , sin , zero,
+            zero, -sin, cos , zero,
+            zero, zero, zero, one ,
+        ])
+    }
+
+    /// A counter-clockwise rotation around the Y axis. `angle` is in radians.
+    #[rustfmt::skip]
+    pub fn rotation_y(angle: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        let (sin, cos) = angle.sin_cos();
+        Self([
+            cos , zero, -sin, zero,
+            zero, one , zero, zero,
+            sin , zero, cos , zero,
+            zero, zero, zero, one ,
+        ])
+    }
+
+    /// A counter-clockwise rotation around the Z axis, which is the rotation
+    /// used for 2D content. `angle` is in radians.
+    #[rustfmt::skip]
+    pub fn rotation_z(angle: S) -> Self {
+        let zero = S::zero();
+        let one = S::one();
+        let (sin, cos) = angle.sin_cos();
+        Self([
+            cos , sin , zero, zero,
+            -sin, cos , zero, zero,
+            zero, zero, one , zero,
+            zero, zero, zero, one ,
+        ])
+    }
+
+    /// Returns the inverse of this transformation, or `None` if it is not
+    /// invertible.
+    pub fn invert(&self) -> Option<Self> {
+        let [a00, a01, a02, a03, a10, a11, a12, a13, a20, a21, a22, a23, a30, a31, a32, a33] =
+            self.0;
+
+        let b00 = a00 * a11 - a01 * a10;
+        let b01 = a00 * a12 - a02 * a10;
+        let b02 = a00 * a13 - a03 * a10;
+        let b03 = a01 * a12 - a02 * a11;
+        let b04 = a01 * a13 - a03 * a11;
+        let b05 = a02 * a13 - a03 * a12;
+        let b06 = a20 * a31 - a21 * a30;
+        let b07 = a20 * a32 - a22 * a30;
+        let b08 = a20 * a33 - a23 * a30;
+        let b09 = a21 * a32 - a22 * a31;
+        let b10 = a21 * a33 - a23 * a31;
+        let b11 = a22 * a33 - a23 * a32;
+
+        let determinant = b00 * b11 - b01 * b10 + b02 * b09 + b03 * b08 - b04 * b07 + b05 * b06;
+        if determinant == S::zero() || !determinant.is_finite() {
+            return None;
+        }
+        let inverse = determinant.recip();
+
+        Some(Self(
+            [
+                a11 * b11 - a12 * b10 + a13 * b09,
+                a02 * b10 - a01 * b11 - a03 * b09,
+                a31 * b05 - a32 * b04 + a33 * b03,
+                a22 * b04 - a21 * b05 - a23 * b03,
+                a12 * b08 - a10 * b11 - a13 * b07,
+                a00 * b11 - a02 * b08 + a03 * b07,
+                a32 * b02 - a30 * b05 - a33 * b01,
+                a20 * b05 - a22 * b02 + a23 * b01,
+                a10 * b10 - a11 * b08 + a13 * b06,
+                a01 * b08 - a00 * b10 - a03 * b06,
+                a30 * b04 - a31 * b02 + a33 * b00,
+                a21 * b02 - a20 * b04 - a23 * b00,
+                a11 * b07 - a10 * b09 - a12 * b06,
+                a00 * b09 - a01 * b07 + a02 * b06,
+                a31 * b01 - a30 * b03 - a32 * b00,
+                a20 * b03 - a21 * b01 + a22 * b00,
+            ]
+            .map(|value| value * inverse),
+        ))
+    }
+
+    /// Transforms `point`, dividing the result by its `w` component.
+    pub fn to_screen(&self, point: [S; 3]) -> [S; 3] {
+        let [x, y, z] = point;
+        let m = &self.0;
+        let transformed: [S; 4] =
+            std::array::from_fn(|row| m[row] * x + m[4 + row] * y + m[8 + row] * z + m[12 + row]);
+        let [x, y, z, w] = transformed;
+        [x / w, y / w, z / w]
+    }
+
+    /// Transforms `point` by the inverse of this transformation, undoing
+    /// `to_screen`. Returns `None` if this transformation is not invertible.
+    pub fn from_screen(&self, point: [S; 3]) -> Option<[S; 3]> {
+        self.invert().map(|inverse| inverse.to_screen(point))
+    }
+}
+
+/// Composes two transformations. `a * b` applies `b` first, then `a`.
+impl<S> Mul for ScreenTransformation<S>
+where
+    S: Add<S, Output = S> + Mul<S, Output = S> + Zero + Copy,
+{
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        // Both matrices are stored column-major: element (row, column) is at
+        // `column * 4 + row`.
+        let mut result = [S::zero(); 16];
+        for (index, value) in result.iter_mut().enumerate() {
+            let (column, row) = (index / 4, index % 4);
+            *value = (0..4).fold(S::zero(), |sum, k| {
+                sum + self.0[k * 4 + row] * rhs.0[column * 4 + k]
+            });
+        }
+        Self(result)
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<glam::Mat4> for ScreenTransformation<f32> {
+    fn from(matrix: glam::Mat4) -> Self {
+        Self(matrix.to_cols_array())
+    }
+}
+
+#[cfg(feature = "glam")]
+impl From<ScreenTransformation<f32>> for glam::Mat4 {
+    fn from(transformation: ScreenTransformation<f32>) -> Self {
+        glam::Mat4::from_cols_array(&transformation.0)
+    }
+}
+
+#[cfg(feature = "mint")]
+impl<S: Copy> From<mint::ColumnMatrix4<S>> for ScreenTransformation<S> {
+    fn from(matrix: mint::ColumnMatrix4<S>) -> Self {
+        Self(matrix.into())
+    }
+}
+
+#[cfg(feature = "mint")]
+impl<S: Copy> From<ScreenTransformation<S>> for mint::ColumnMatrix4<S> {
+    fn from(transformation: ScreenTransformation<S>) -> Self {
+        transformation.0.into()
+    }
+}
+
 impl<S> ScreenTransformation<S> {
     #[rustfmt::skip]
     pub fn to_array(self) -> [S; 16] {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: [f32; 16], expected: [f32; 16]) {
+        for (a, e) in actual.into_iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{actual:?} != {expected:?}");
+        }
+    }
+
+    fn transformations() -> [ScreenTransformation<f32>; 4] {
+        [
+            ScreenTransformation::ortho(0., 0., 800., 600., -1., 1.),
+            ScreenTransformation::perspective_reverse_z(1.2, 4. / 3., 0.1, 100.),
+            ScreenTransformation::translation(3., -2., 5.)
+                * ScreenTransformation::rotation_z(0.7)
+                * ScreenTransformation::scale(2., 0.5, 1.),
+            ScreenTransformation::rotation_x(0.3) * ScreenTransformation::rotation_y(-1.1),
+        ]
+    }
+
+    #[test]
+    fn inverse_composes_to_identity() {
+        let identity = ScreenTransformation::<f32>::identity().to_array();
+        for t in transformations() {
+            let inverse = t.invert().unwrap();
+            assert_near((t * inverse).to_array(), identity);
+            assert_near((inverse * t).to_array(), identity);
+        }
+    }
+
+    #[test]
+    fn singular_has_no_inverse() {
+        assert!(ScreenTransformation::scale(1., 0., 1.).invert().is_none());
+    }
+
+    #[test]
+    fn mul_applies_right_first() {
+        let t = ScreenTransformation::translation(10., 0., 0.)
+            * ScreenTransformation::scale(2., 2., 2.);
+        assert_eq!(t.to_screen([1., 1., 1.]), [12., 2., 2.]);
+    }
+
+    #[test]
+    fn point_round_trip() {
+        let point = [120., 45., -0.5];
+        for t in transformations() {
+            let back = t.from_screen(t.to_screen(point)).unwrap();
+            for (b, p) in back.into_iter().zip(point) {
+                assert!((b - p).abs() < 1e-3, "{back:?} != {point:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn ortho_maps_corners_to_clip_space() {
+        let t = ScreenTransformation::ortho(0., 0., 800., 600., -1., 1.);
+        assert_eq!(t.to_screen([0., 0., 0.]), [-1., 1., 0.]);
+        assert_eq!(t.to_screen([800., 600., 0.]), [1., -1., 0.]);
+    }
+}
@@ -30,18 +30,52 @@ impl From<VertexFormat> for wgpu::VertexFormat {
     }
 }
 
-#[derive(Default, Debug)]
+/// Describes the layout of a single vertex buffer slot in a pipeline.
+///
+/// The slot index is the position of the description within
+/// `PipelineDescription::vertex_layout`.
+#[derive(Debug, Clone, Copy)]
+pub struct VertexBufferDesc<'a> {
+    pub formats: &'a [VertexFormat],
+    pub step_mode: wgpu::VertexStepMode,
+}
+
+#[derive(Debug)]
 pub struct VertexLayout {
     attributes: Vec<wgpu::VertexAttribute>,
     size: usize,
+    step_mode: wgpu::VertexStepMode,
+}
+
+impl Default for VertexLayout {
+    fn default() -> Self {
+        Self {
+            attributes: Vec::new(),
+            size: 0,
+            step_mode: wgpu::VertexStepMode::Vertex,
+        }
+    }
 }
 
 impl VertexLayout {
     pub fn from(vertex_formats: &[VertexFormat]) -> Self {
-        let mut layouts: Self = Self::default();
+        Self::new(vertex_formats, wgpu::VertexStepMode::Vertex, 0)
+    }
+
+    /// Creates a layout whose attributes are assigned shader locations
+    /// starting at `first_location`.
+    pub fn new(
+        vertex_formats: &[VertexFormat],
+        step_mode: wgpu::VertexStepMode,
+        first_location: u32,
+    ) -> Self {
+        let mut layouts = Self {
+            step_mode,
+            ..Self::default()
+        };
         for format in vertex_formats {
             layouts.attributes.push(wgpu::VertexAttribute {
-                shader_location: layouts.attributes.len() as u32,
+                shader_location: first_location + layouts.attributes.len() as u32,
                 offset: layouts.size as wgpu::BufferAddress,
                 format: (*format).into(),
             });
@@ -49,18 +83,32 @@ impl VertexLayout {
         }
         layouts
     }
+
+    /// Creates one layout per buffer slot. Shader locations are numbered
+    /// sequentially across all slots, in order.
+    pub fn from_buffers(buffers: &[VertexBufferDesc]) -> Vec<Self> {
+        let mut layouts = Vec::with_capacity(buffers.len());
+        let mut next_location = 0;
+        for desc in buffers {
+            let layout = Self::new(desc.formats, desc.step_mode, next_location);
+            next_location += layout.attributes.len() as u32;
+            layouts.push(layout);
+        }
+        layouts
+    }
 }
 
 impl<'a> From<&'a VertexLayout> for wgpu::VertexBufferLayout<'a> {
     fn from(layout: &'a VertexLayout) -> Self {
         wgpu::VertexBufferLayout {
             array_stride: layout.size as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
+            step_mode: layout.step_mode,
             attributes: layout.attributes.as_slice(),
         }
     }
 }
 
+#[deprecated(note = "use `buffers::VertexBuffer`")]
 #[derive(Debug, Clone)]
 pub struct VertexBuffer {
     pub size: u64,
@@ -0,0 +1,11 @@
+use easygpu::prelude::*;
+
+/// An offscreen renderer, or `None` if the machine has no adapter.
+pub fn renderer() -> Option<Renderer<'static>> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+    RendererBuilder::new()
+        .with_offscreen(true, adapter, 1)
+        .build_blocking()
+        .ok()
+}
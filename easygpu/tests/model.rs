@@ -0,0 +1,171 @@
+use easygpu::gltf::image::Format;
+use easygpu::prelude::*;
+
+mod common;
+
+/// A 1x1 RGBA PNG.
+const RGBA_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xf0,
+    0x1f, 0x00, 0x05, 0x00, 0x01, 0xff, 0x89, 0x99, 0x3d, 0x1d, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45,
+    0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// A 1x1 RGB PNG.
+const RGB_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xde, 0x00, 0x00, 0x00, 0x0c, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x68, 0x68, 0xf8, 0x0f,
+    0x00, 0x03, 0x83, 0x02, 0x00, 0x00, 0xa5, 0xfb, 0x89, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e,
+    0x44, 0xae, 0x42, 0x60, 0x82,
+];
+
+/// A 1x1 16-bit grayscale PNG, which the importer doesn't support.
+const GRAY16_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x10, 0x00, 0x00, 0x00, 0x00, 0x6a, 0xee, 0x47,
+    0x16, 0x00, 0x00, 0x00, 0x0b, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x60, 0x64, 0x02, 0x00,
+    0x00, 0x07, 0x00, 0x04, 0x76, 0x49, 0xe3, 0x28, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44,
+    0xae, 0x42, 0x60, 0x82,
+];
+
+/// Pads `bytes` to a multiple of 4 with `padding`, as GLB chunks require.
+fn padded(mut bytes: Vec<u8>, padding: u8) -> Vec<u8> {
+    bytes.resize(bytes.len().next_multiple_of(4), padding);
+    bytes
+}
+
+/// A GLB file with one triangle with 16-bit indices, using the second of two
+/// materials, followed by `images` in its binary chunk. The triangle's
+/// material uses the first image as its base color and the second, if there
+/// is one, as its normal map.
+fn glb(images: &[&[u8]]) -> Vec<u8> {
+    let positions: [[f32; 3]; 3] = [[0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+    let indices: [u16; 3] = [0, 2, 1];
+    let mut bin = bytemuck::cast_slice::<_, u8>(&positions).to_vec();
+    bin.extend_from_slice(bytemuck::cast_slice(&indices));
+
+    let mut views = vec![
+        String::from(r#"{"buffer": 0, "byteOffset": 0, "byteLength": 36}"#),
+        String::from(r#"{"buffer": 0, "byteOffset": 36, "byteLength": 6}"#),
+    ];
+    let mut image_json = Vec::new();
+    for image in images {
+        bin = padded(bin, 0);
+        views.push(format!(
+            r#"{{"buffer": 0, "byteOffset": {}, "byteLength": {}}}"#,
+            bin.len(),
+            image.len()
+        ));
+        image_json.push(format!(
+            r#"{{"bufferView": {}, "mimeType": "image/png"}}"#,
+            views.len() - 1
+        ));
+        bin.extend_from_slice(image);
+    }
+    let bin = padded(bin, 0);
+    let textures = (0..images.len())
+        .map(|index| format!(r#"{{"source": {index}}}"#))
+        .collect::<Vec<_>>();
+    let normal = if images.len() > 1 {
+        r#", "normalTexture": {"index": 1}"#
+    } else {
+        ""
+    };
+
+    let json = format!(
+        r#"{{
+            "asset": {{"version": "2.0"}},
+            "buffers": [{{"byteLength": {}}}],
+            "bufferViews": [{}],
+            "accessors": [
+                {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0, 0, 0], "max": [1, 1, 0]}},
+                {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+            ],
+            "images": [{}],
+            "textures": [{}],
+            "materials": [
+                {{"pbrMetallicRoughness": {{"baseColorFactor": [1, 0, 0, 1]}}}},
+                {{"pbrMetallicRoughness": {{"baseColorFactor": [0, 1, 0, 0.5],
+                  "baseColorTexture": {{"index": 0}}}}{normal}}}
+            ],
+            "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1,
+                "material": 1}}]}}]
+        }}"#,
+        bin.len(),
+        views.join(", "),
+        image_json.join(", "),
+        textures.join(", "),
+    );
+    let json = padded(json.into_bytes(), b' ');
+
+    let mut glb = Vec::new();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&(12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+    glb
+}
+
+#[test]
+fn meshes_and_materials_are_loaded() {
+    let Some(renderer) = common::renderer() else {
+        return;
+    };
+    let model = Model::load_gltf_slice(&renderer.device, &glb(&[RGBA_PNG, RGB_PNG])).unwrap();
+
+    assert_eq!(model.primitives.len(), 1);
+    let primitive = &model.primitives[0];
+    assert_eq!(primitive.material, Some(1));
+    assert_eq!(
+        primitive.mesh.vertices.size as usize,
+        3 * std::mem::size_of::<ModelVertex>()
+    );
+    // 16-bit indices are widened to 32 bits.
+    assert_eq!(primitive.mesh.indices.format, wgpu::IndexFormat::Uint32);
+    assert_eq!(primitive.mesh.indices.elements, 3);
+    assert_eq!(primitive.mesh.range, 0..3);
+
+    assert_eq!(
+        model.materials,
+        [
+            Material {
+                base_color: Rgba::new(1., 0., 0., 1.),
+                base_color_texture: None,
+            },
+            Material {
+                base_color: Rgba::new(0., 1., 0., 0.5),
+                base_color_texture: Some(0),
+            },
+        ]
+    );
+
+    // Only the base color is sRGB-encoded; the normal map is linear.
+    assert_eq!(model.textures.len(), 2);
+    assert_eq!(
+        model.textures[0].format,
+        wgpu::TextureFormat::Rgba8UnormSrgb
+    );
+    assert_eq!(model.textures[1].format, wgpu::TextureFormat::Rgba8Unorm);
+}
+
+#[test]
+fn unsupported_image_formats_are_errors() {
+    let Some(renderer) = common::renderer() else {
+        return;
+    };
+    match Model::load_gltf_slice(&renderer.device, &glb(&[GRAY16_PNG])) {
+        Err(ImportError::UnsupportedImageFormat { image, format }) => {
+            assert_eq!(image, 0);
+            assert_eq!(format, Format::R16);
+        }
+        result => panic!("expected UnsupportedImageFormat, got {:?}", result),
+    }
+}
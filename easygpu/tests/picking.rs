@@ -0,0 +1,70 @@
+use std::future::Future;
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use easygpu::figures::Size;
+use easygpu::picking::IdBuffer;
+use easygpu::prelude::*;
+
+mod common;
+
+/// An id buffer with every texel set to `id`.
+fn id_buffer(renderer: &Renderer, id: u32) -> IdBuffer {
+    let ids = renderer.device.id_buffer(Size::new(4, 4));
+    let mut encoder = renderer.device.create_command_encoder();
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &ids.texture.view,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: wgpu::LoadOp::Clear(wgpu::Color {
+                    r: f64::from(id),
+                    ..wgpu::Color::TRANSPARENT
+                }),
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    renderer.device.submit(Some(encoder.finish()));
+    ids
+}
+
+/// Polls the device until `future` resolves.
+fn wait<F: Future>(renderer: &Renderer, future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        renderer.poll(wgpu::Maintain::Wait);
+    }
+}
+
+#[test]
+fn picks_can_be_pending_at_once() {
+    let Some(renderer) = common::renderer() else {
+        return;
+    };
+    let ids = id_buffer(&renderer, 7);
+    let first = renderer.pick(&ids, 1, 1);
+    let second = renderer.pick(&ids, 2, 2);
+    assert_eq!(wait(&renderer, second), Ok(Some(7)));
+    assert_eq!(wait(&renderer, first), Ok(Some(7)));
+}
+
+#[test]
+fn dropped_picks_release_their_buffer() {
+    let Some(renderer) = common::renderer() else {
+        return;
+    };
+    let ids = id_buffer(&renderer, 3);
+    drop(renderer.pick(&ids, 1, 1));
+    renderer.wait_idle();
+    assert_eq!(renderer.pick_blocking(&ids, 1, 1), Ok(Some(3)));
+    assert_eq!(renderer.pick_blocking(&ids, 9, 1), Ok(None));
+}
@@ -0,0 +1,76 @@
+//! Benchmarks of tessellating shapes and uploading them with the headless
+//! renderer. Run with `cargo bench -p easygpu-lyon --features blocking`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use easygpu::prelude::*;
+use easygpu_lyon::{tessellate, ShapePath};
+use lyon_tessellation::math::point;
+use lyon_tessellation::path::{Path, Winding};
+use lyon_tessellation::StrokeOptions;
+
+fn circles(count: usize) -> Vec<Path> {
+    (0..count)
+        .map(|index| {
+            let mut builder = Path::builder();
+            builder.add_circle(
+                point((index % 32) as f32 * 16., (index / 32) as f32 * 16.),
+                6.,
+                Winding::Positive,
+            );
+            builder.build()
+        })
+        .collect()
+}
+
+fn shape_paths(paths: &[Path]) -> impl Iterator<Item = ShapePath<'_>> {
+    let stroke = StrokeOptions::default().with_line_width(2.);
+    paths.iter().flat_map(move |path| {
+        [
+            ShapePath::fill(path, [1., 0., 0., 1.]),
+            ShapePath::stroke(path, stroke, [1., 1., 1., 1.]),
+        ]
+    })
+}
+
+fn tessellation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tessellate");
+    for count in [10, 100, 1_000] {
+        let paths = circles(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &paths, |b, paths| {
+            b.iter(|| tessellate(shape_paths(paths)).expect("error tessellating circles"));
+        });
+    }
+    group.finish();
+}
+
+fn upload(c: &mut Criterion) {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let Some(adapter) = futures::executor::block_on(
+        instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+    ) else {
+        eprintln!("no adapter found, skipping upload benchmarks");
+        return;
+    };
+    let renderer = RendererBuilder::new()
+        .with_offscreen(true, adapter, 1)
+        .build_blocking()
+        .expect("error creating renderer");
+
+    let mut group = c.benchmark_group("tessellate_and_upload");
+    for count in [10, 100, 1_000] {
+        let paths = circles(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &paths, |b, paths| {
+            b.iter(|| {
+                tessellate(shape_paths(paths))
+                    .expect("error tessellating circles")
+                    .upload(&renderer)
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, tessellation, upload);
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+use easygpu::figures::Size;
+use easygpu::prelude::*;
+use easygpu_lyon::{ClipPipeline, ShapeBuilder, Srgb, VertexShaderSource};
+use lyon_tessellation::math::{point, Box2D, Point};
+use lyon_tessellation::path::{Path, Winding};
+use lyon_tessellation::FillOptions;
+
+mod common;
+
+const SIZE: Size<u32> = Size::new(128, 128);
+
+/// Draws a circle inside two nested clips without a window and writes it to a
+/// PPM image.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Clipping uses the stencil aspect of the depth buffer.
+    let mut renderer = common::offscreen()
+        .await?
+        .with_depth_format(wgpu::TextureFormat::Depth24PlusStencil8)
+        .build()
+        .await?;
+
+    let pipeline: ClipPipeline<Srgb> =
+        ClipPipeline::new(&renderer, Blending::default(), Srgb::sampler_format());
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(SIZE, common::MSAA_SAMPLE_COUNT),
+    );
+
+    let rectangle = |min: Point, max: Point| {
+        let mut builder = Path::builder();
+        builder.add_rectangle(&Box2D::new(min, max), Winding::Positive);
+        builder.build()
+    };
+    let mut circle = Path::builder();
+    circle.add_circle(point(64., 64.), 56., Winding::Positive);
+    let circle = circle.build();
+
+    let mut builder = ShapeBuilder::default();
+    // A panel clipped to the left half, with a clip of the top half inside it
+    // leaving only the top left quarter of the circle.
+    builder.push_clip(
+        &rectangle(point(0., 0.), point(64., 128.)),
+        &FillOptions::default(),
+    )?;
+    builder.push_clip(
+        &rectangle(point(0., 0.), point(128., 64.)),
+        &FillOptions::default(),
+    )?;
+    builder.default_color = [1., 0., 0., 1.];
+    builder.fill(&circle, &FillOptions::default())?;
+    builder.pop_clip();
+    // Only the left clip applies to the second circle.
+    builder.default_color = [0., 0., 1., 1.];
+    let mut small = Path::builder();
+    small.add_circle(point(64., 96.), 24., Winding::Positive);
+    builder.fill(&small.build(), &FillOptions::default())?;
+    builder.pop_clip();
+    let shape = builder.prepare(&renderer);
+
+    let framebuffer = renderer.render_offscreen(SIZE, Srgb::sampler_format(), |frame| {
+        let mut pass = frame.pass(PassOp::Clear(Rgba::new(0., 0., 0., 1.)));
+        pipeline.draw(&shape, &mut pass);
+    });
+
+    common::write_ppm(&mut renderer, &framebuffer, "clip")
+}
@@ -0,0 +1,39 @@
+use std::io::Write;
+
+use easygpu::prelude::*;
+
+// Software adapters, which are common when rendering headlessly, often lack
+// multisampling support.
+pub const MSAA_SAMPLE_COUNT: u32 = 1;
+
+/// Starts building a renderer without a window, on the default adapter.
+pub async fn offscreen() -> anyhow::Result<RendererBuilder<'static>> {
+    env_logger::init();
+    let instance = easygpu::wgpu::Instance::new(&easygpu::wgpu::InstanceDescriptor::default());
+    let adapter = instance
+        .request_adapter(&easygpu::wgpu::RequestAdapterOptions::default())
+        .await
+        .ok_or_else(|| anyhow::anyhow!("no suitable adapter found"))?;
+    Ok(RendererBuilder::new().with_offscreen(true, adapter, MSAA_SAMPLE_COUNT))
+}
+
+/// Reads back `framebuffer` and writes it to `easygpu-{name}.ppm` in the
+/// temporary directory.
+pub fn write_ppm(
+    renderer: &mut Renderer,
+    framebuffer: &Framebuffer,
+    name: &str,
+) -> anyhow::Result<()> {
+    let size = framebuffer.texture.size;
+    let texels: Vec<Rgba8> = renderer.read_to_vec(framebuffer, ReadOptions::default())?;
+    let mut image = Vec::with_capacity(texels.len() * 3 + 32);
+    write!(image, "P6\n{} {}\n255\n", size.width, size.height)?;
+    for texel in texels {
+        image.extend_from_slice(&[texel.r, texel.g, texel.b]);
+    }
+    let path = std::env::temp_dir().join(format!("easygpu-{}.ppm", name));
+    std::fs::write(&path, image)?;
+    println!("Wrote {}", path.display());
+
+    Ok(())
+}
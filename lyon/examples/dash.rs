@@ -0,0 +1,51 @@
+use easygpu::figures::Size;
+use easygpu::prelude::*;
+use easygpu_lyon::{DashBuilder, DashPattern, DashPipeline, Srgb, VertexShaderSource};
+use lyon_tessellation::math::{point, Box2D};
+use lyon_tessellation::path::{Path, Winding};
+use lyon_tessellation::{LineCap, StrokeOptions};
+
+mod common;
+
+const SIZE: Size<u32> = Size::new(128, 128);
+
+/// Draws a dashed selection outline without a window and writes it to a PPM
+/// image.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut renderer = common::offscreen().await?.build().await?;
+
+    let pipeline: DashPipeline = renderer.pipeline(Blending::default(), Srgb::sampler_format());
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(SIZE, common::MSAA_SAMPLE_COUNT),
+    );
+
+    let pattern = DashPattern::new(vec![8., 4.], 0.);
+    let material = pipeline.material(&renderer, &pattern);
+    // Marching ants move the pattern a little every frame.
+    material.set_pattern(&renderer, &pattern.with_offset(3.));
+
+    let mut outline = Path::builder();
+    outline.add_rectangle(
+        &Box2D::new(point(16., 16.), point(112., 112.)),
+        Winding::Positive,
+    );
+    let mut builder = DashBuilder::new(0., [1., 1., 1., 1.]);
+    builder.stroke(
+        &outline.build(),
+        &StrokeOptions::default()
+            .with_line_width(2.)
+            .with_line_cap(LineCap::Butt),
+    )?;
+    let shape = builder.prepare(&renderer);
+
+    let framebuffer = renderer.render_offscreen(SIZE, Srgb::sampler_format(), |frame| {
+        let mut pass = frame.pass(PassOp::Clear(Rgba::new(0., 0., 0., 1.)));
+        pass.set_easy_pipeline(&pipeline);
+        pass.set_binding(&material.binding, &[]);
+        shape.draw(&mut pass);
+    });
+
+    common::write_ppm(&mut renderer, &framebuffer, "dash")
+}
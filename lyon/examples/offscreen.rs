@@ -0,0 +1,42 @@
+use easygpu::figures::Size;
+use easygpu::prelude::*;
+use easygpu_lyon::{LyonPipeline, ShapeBuilder, Srgb, VertexShaderSource};
+use lyon_tessellation::math::Point;
+use lyon_tessellation::{FillOptions, FillTessellator};
+
+mod common;
+
+const SIZE: Size<u32> = Size::new(128, 128);
+
+/// Renders a circle without a window and writes it to a PPM image.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut renderer = common::offscreen().await?.build().await?;
+
+    let pipeline: LyonPipeline<Srgb> =
+        renderer.pipeline(Blending::default(), Srgb::sampler_format());
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(SIZE, common::MSAA_SAMPLE_COUNT),
+    );
+
+    let mut builder = ShapeBuilder::default();
+    builder.default_color = [1., 0., 0., 1.];
+    FillTessellator::new()
+        .tessellate_circle(
+            Point::new(64., 64.),
+            48.,
+            &FillOptions::default(),
+            &mut builder,
+        )
+        .expect("Error tesselating circle");
+    let shape = builder.prepare(&renderer);
+
+    let framebuffer = renderer.render_offscreen(SIZE, Srgb::sampler_format(), |frame| {
+        let mut pass = frame.pass(PassOp::Clear(Rgba::new(0., 0., 0., 1.)));
+        pass.set_easy_pipeline(&pipeline);
+        shape.draw(&mut pass);
+    });
+
+    common::write_ppm(&mut renderer, &framebuffer, "offscreen")
+}
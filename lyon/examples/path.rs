@@ -10,7 +10,7 @@ use sandbox::Sandbox;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    PathExample::run().await?;    
+    PathExample::run().await?;
     Ok(())
 }
 
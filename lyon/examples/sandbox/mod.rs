@@ -25,7 +25,8 @@ pub trait Sandbox: Sized + 'static {
         let surface = instance.create_surface(&window)?;
         let mut renderer = RendererBuilder::new()
             .with_surface(surface, instance, MSAA_SAMPLE_COUNT)
-            .build().await?;
+            .build()
+            .await?;
         let sandbox = Self::create(&renderer);
         let size = Size::new(size.width, size.height).cast::<u32>();
 
@@ -38,64 +39,65 @@ pub trait Sandbox: Sized + 'static {
             MSAA_SAMPLE_COUNT > 1,
         );
 
-        event_loop.run(move |event, control_flow| match event {
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    control_flow.exit();
-                }
-                WindowEvent::Resized(new_size) => {
-                    let new_size = Size::new(new_size.width, new_size.height).cast::<u32>();
-                    renderer.configure(new_size, PresentMode::Fifo, Srgb::sampler_format());
-                    // Recreate the texture to match the new output size.
-                    multisample_texture = renderer.texture(
-                        new_size,
-                        Srgb::sampler_format(),
-                        TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
-                        MSAA_SAMPLE_COUNT > 1,
-                    );
-                }
-                WindowEvent::KeyboardInput {
-                    event:
-                        KeyEvent {
-                            state: ElementState::Pressed,
-                            physical_key: PhysicalKey::Code(KeyCode::Escape),
-                            ..
-                        },
-                    ..
-                } => {}
-                WindowEvent::RedrawRequested => {
-                    if let Ok(output) = renderer.current_frame() {
-                        let mut frame = renderer.frame();
+        event_loop.run(move |event, control_flow| {
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CloseRequested => {
+                        control_flow.exit();
+                    }
+                    WindowEvent::Resized(new_size) => {
+                        let new_size = Size::new(new_size.width, new_size.height).cast::<u32>();
+                        renderer.configure(new_size, PresentMode::Fifo, Srgb::sampler_format());
+                        // Recreate the texture to match the new output size.
+                        multisample_texture = renderer.texture(
+                            new_size,
+                            Srgb::sampler_format(),
+                            TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                            MSAA_SAMPLE_COUNT > 1,
+                        );
+                    }
+                    WindowEvent::KeyboardInput {
+                        event:
+                            KeyEvent {
+                                state: ElementState::Pressed,
+                                physical_key: PhysicalKey::Code(KeyCode::Escape),
+                                ..
+                            },
+                        ..
+                    } => {}
+                    WindowEvent::RedrawRequested => {
+                        if let Ok(output) = renderer.current_frame() {
+                            let mut frame = renderer.frame();
 
-                        let pipeline = sandbox.pipeline();
+                            let pipeline = sandbox.pipeline();
 
-                        renderer.update_pipeline(
-                            pipeline,
-                            ScreenTransformation::ortho(
-                                0.,
-                                0.,
-                                output.size.width as f32,
-                                output.size.height as f32,
-                                -1.,
-                                1.,
-                            ),
-                        );
-    
-                        {
-                            let mut pass = frame.pass(
-                                PassOp::Clear(Rgba::TRANSPARENT),
-                                &output,
-                                Some(&multisample_texture.view),
+                            renderer.update_pipeline(
+                                pipeline,
+                                ScreenTransformation::ortho(
+                                    0.,
+                                    0.,
+                                    output.size.width as f32,
+                                    output.size.height as f32,
+                                    -1.,
+                                    1.,
+                                ),
                             );
-    
-                            sandbox.render(&mut pass);
+
+                            {
+                                let mut pass = frame.pass(
+                                    PassOp::Clear(Rgba::TRANSPARENT),
+                                    &output,
+                                    Some(&multisample_texture.view),
+                                );
+
+                                sandbox.render(&mut pass);
+                            }
+                            renderer.present(frame);
                         }
-                        renderer.present(frame);
                     }
+                    _ => {}
                 }
-                _ => {}
-            },
-            _ => {}
+            }
         })?;
 
         Ok(())
@@ -0,0 +1,70 @@
+use easygpu::figures::{Point, Rect, Size};
+use easygpu::prelude::*;
+use easygpu_lyon::{SdfBitmap, SdfPipeline, SdfQuads, SdfStyle, Srgb, VertexShaderSource};
+
+mod common;
+
+const SIZE: Size<u32> = Size::new(128, 128);
+const ATLAS_SIZE: Size<u32> = Size::new(64, 64);
+const SPREAD: u32 = 8;
+
+/// Scales up a small distance field of a circle without a window and writes it
+/// to a PPM image.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut renderer = common::offscreen().await?.build().await?;
+
+    let pipeline: SdfPipeline = renderer.pipeline(Blending::default(), Srgb::sampler_format());
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(SIZE, common::MSAA_SAMPLE_COUNT),
+    );
+
+    // A circle with a margin of `SPREAD` pixels, as a glyph rasterizer would
+    // produce it.
+    let center = (ATLAS_SIZE.width / 2) as f32;
+    let radius = center - SPREAD as f32;
+    let alpha = (0..ATLAS_SIZE.area())
+        .map(|index| {
+            let x = (index % ATLAS_SIZE.width) as f32 + 0.5 - center;
+            let y = (index / ATLAS_SIZE.width) as f32 + 0.5 - center;
+            if x.hypot(y) <= radius {
+                255
+            } else {
+                0
+            }
+        })
+        .collect::<Vec<u8>>();
+    let atlas = SdfBitmap::from_alpha(&alpha, ATLAS_SIZE, SPREAD).to_texture(&mut renderer);
+    let sampler = renderer.sampler(wgpu::FilterMode::Linear, wgpu::FilterMode::Linear);
+    let material = pipeline.material(
+        &renderer,
+        &atlas,
+        &sampler,
+        &SdfStyle {
+            color: Rgba::new(1., 0.8, 0., 1.),
+            outline_color: Rgba::new(0.6, 0., 0., 1.),
+            outline_width: 0.1,
+            glow_color: Rgba::new(1., 1., 1., 0.5),
+            glow_width: 0.2,
+            ..SdfStyle::default()
+        },
+    );
+
+    let mut quads = SdfQuads::default();
+    quads.push(
+        Rect::new(Point::new(0., 0.), Size::new(128., 128.)),
+        Rect::new(Point::new(0., 0.), Size::new(1., 1.)),
+        0.,
+    );
+    let quads = quads.prepare(&renderer);
+
+    let framebuffer = renderer.render_offscreen(SIZE, Srgb::sampler_format(), |frame| {
+        let mut pass = frame.pass(PassOp::Clear(Rgba::new(0., 0., 0., 1.)));
+        pass.set_easy_pipeline(&pipeline);
+        pass.set_binding(&material.binding, &[]);
+        pass.draw_mesh(&quads);
+    });
+
+    common::write_ppm(&mut renderer, &framebuffer, "sdf")
+}
@@ -0,0 +1,39 @@
+use easygpu::figures::Size;
+use easygpu::prelude::*;
+use easygpu_lyon::{load_svg, LyonPipeline, Srgb, VertexShaderSource};
+
+mod common;
+
+const SIZE: Size<u32> = Size::new(128, 128);
+
+const DOCUMENT: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 64 32">
+  <rect x="2" y="2" width="28" height="28" rx="4" fill="#3080ff" stroke="#ffffff" stroke-width="2"/>
+  <g transform="translate(48 16)" opacity="0.8">
+    <circle r="12" fill="#ff4020"/>
+  </g>
+</svg>"##;
+
+/// Draws a small SVG document without a window and writes it to a PPM image.
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut renderer = common::offscreen().await?.build().await?;
+
+    let pipeline: LyonPipeline<Srgb> =
+        renderer.pipeline(Blending::default(), Srgb::sampler_format());
+    renderer.update_pipeline(
+        &pipeline,
+        ScreenTransformation::pixel_perfect(SIZE, common::MSAA_SAMPLE_COUNT),
+    );
+
+    // The document is twice as wide as it is tall, so it is centered
+    // vertically.
+    let shapes = load_svg(&renderer, DOCUMENT.as_bytes(), Size::new(128., 128.))?;
+
+    let framebuffer = renderer.render_offscreen(SIZE, Srgb::sampler_format(), |frame| {
+        let mut pass = frame.pass(PassOp::Clear(Rgba::new(0., 0., 0., 1.)));
+        pass.set_easy_pipeline(&pipeline);
+        shapes.draw(&mut pass);
+    });
+
+    common::write_ppm(&mut renderer, &framebuffer, "svg")
+}
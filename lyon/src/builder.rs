@@ -1,6 +1,8 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 use easygpu::color::Rgba;
+use easygpu::culling::BoundingBox;
 use easygpu::renderer::Renderer;
 use lyon_tessellation::math::Point;
 use lyon_tessellation::path::Path;
@@ -9,7 +11,7 @@ use lyon_tessellation::{
     TessellationError, VertexId,
 };
 
-use crate::shape::{Shape, Vertex};
+use crate::shape::{ClipCommand, ClipOp, Shape, TessellatedVertex, Vertex};
 
 mod lyon_builders;
 
@@ -17,8 +19,13 @@ mod lyon_builders;
 /// Builds a shape using lyon for tesselation
 pub struct ShapeBuilder {
     zdepth: f32,
-    vertices: Vec<Vertex>,
+    vertices: Vec<TessellatedVertex>,
     indicies: Vec<u16>,
+    clipping: Vec<ClipCommand>,
+    /// The index ranges of the clip paths that haven't been popped.
+    clips: Vec<Range<u32>>,
+    /// The first index drawn since the last clip was pushed or popped.
+    unclipped_start: u32,
 
     /// This RGBA color is used when tesselating a path with no color data
     /// (Attributes in lyon terminology)
@@ -42,16 +49,93 @@ impl ShapeBuilder {
     /// Prepare and load this builder into the renderer.
     ///
     /// This does not consume the builder, because wgpu copies the buffer rather
-    /// than taking ownerhip.
+    /// than taking ownerhip. Vertex colors are converted from the renderer's
+    /// `ColorSpace`.
     pub fn prepare(&self, renderer: &Renderer) -> Shape {
-        let verticies = renderer.device.create_buffer(&self.vertices);
-        let indicies = renderer.device.create_index(&self.indicies);
+        upload(renderer, &self.vertices, &self.indicies, self.clipping())
+    }
+
+    /// The tessellated vertices and their triangle indices.
+    pub(crate) fn geometry(&self) -> (&[TessellatedVertex], &[u16]) {
+        (&self.vertices, &self.indicies)
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<TessellatedVertex>, Vec<u16>, Vec<ClipCommand>) {
+        let clipping = self.clipping();
+        (self.vertices, self.indicies, clipping)
+    }
+
+    /// Fills `path` into a new clip region, so shapes added until the
+    /// matching `pop_clip` are only drawn inside it and inside every clip that
+    /// is already active. Clips only apply when the shape is drawn with a
+    /// `ClipPipeline`.
+    pub fn push_clip(
+        &mut self,
+        path: &Path,
+        options: &FillOptions,
+    ) -> Result<(), TessellationError> {
+        self.flush_unclipped();
+        let start = self.indicies.len() as u32;
+        self.fill(path, options)?;
+        let indices = start..self.indicies.len() as u32;
+        self.clipping.push(ClipCommand {
+            indices: indices.clone(),
+            op: ClipOp::Push,
+            depth: self.clips.len() as u32,
+        });
+        self.clips.push(indices);
+        self.unclipped_start = self.indicies.len() as u32;
+        Ok(())
+    }
+
+    /// Removes the clip added by the last `push_clip`. Clips that are still
+    /// active when the shape is prepared are removed after it is drawn.
+    ///
+    /// Panics if there is no active clip.
+    pub fn pop_clip(&mut self) {
+        self.flush_unclipped();
+        let indices = self
+            .clips
+            .pop()
+            .expect("pop_clip called without a matching push_clip");
+        self.clipping.push(ClipCommand {
+            indices,
+            op: ClipOp::Pop,
+            depth: self.clips.len() as u32 + 1,
+        });
+    }
+
+    /// Records the indices added since the last clip command as a draw.
+    fn flush_unclipped(&mut self) {
+        self.clipping.extend(self.pending_draw());
+        self.unclipped_start = self.indicies.len() as u32;
+    }
+
+    fn pending_draw(&self) -> Option<ClipCommand> {
+        let end = self.indicies.len() as u32;
+        (self.unclipped_start < end).then_some(ClipCommand {
+            indices: self.unclipped_start..end,
+            op: ClipOp::Draw,
+            depth: self.clips.len() as u32,
+        })
+    }
 
-        Shape {
-            index_count: self.indicies.len() as u32,
-            vertices: Arc::new(verticies),
-            indices: Arc::new(indicies),
+    /// The recorded clip commands, followed by the final draw and the pops of
+    /// any clips left active.
+    fn clipping(&self) -> Vec<ClipCommand> {
+        if self.clipping.is_empty() {
+            return Vec::new();
         }
+        let mut clipping = self.clipping.clone();
+        clipping.extend(self.pending_draw());
+        for (depth, indices) in self.clips.iter().enumerate().rev() {
+            clipping.push(ClipCommand {
+                indices: indices.clone(),
+                op: ClipOp::Pop,
+                depth: depth as u32 + 1,
+            });
+        }
+        clipping
     }
 
     /// Fill an arbitrary path from `lyon::path`
@@ -72,7 +156,7 @@ impl ShapeBuilder {
         Ok(())
     }
 
-    fn new_vertex(&mut self, point: Point, attributes: &[f32]) -> Vertex {
+    fn new_vertex(&mut self, point: Point, attributes: &[f32]) -> TessellatedVertex {
         let attributes = if attributes.is_empty() {
             &self.default_color
         } else {
@@ -81,14 +165,13 @@ impl ShapeBuilder {
 
         assert!(attributes.len() == 4, "Attributes should be RGBA");
 
-        Vertex {
+        TessellatedVertex {
             color: Rgba {
                 r: attributes[0],
                 g: attributes[1],
                 b: attributes[2],
                 a: attributes[3],
-            }
-            .into(),
+            },
             position: [point.x, point.y, self.zdepth],
         }
     }
@@ -108,3 +191,143 @@ impl ShapeBuilder {
         Ok(new_id)
     }
 }
+
+/// Uploads tessellated vertices, converting their colors from the renderer's
+/// `ColorSpace`.
+pub(crate) fn upload(
+    renderer: &Renderer,
+    vertices: &[TessellatedVertex],
+    indices: &[u16],
+    clipping: Vec<ClipCommand>,
+) -> Shape {
+    let color_space = renderer.color_space();
+    let encoded = vertices
+        .iter()
+        .map(|vertex| vertex.encode(color_space))
+        .collect::<Vec<Vertex>>();
+    Shape {
+        mesh: Arc::new(renderer.mesh(&encoded, indices)),
+        bounds: BoundingBox::from_points(vertices.iter().map(|v| v.position)),
+        clipping,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use easygpu::color::{ColorSpace, Rgba8};
+
+    use super::*;
+
+    fn rect() -> Path {
+        let mut builder = Path::builder();
+        builder.begin(Point::new(0., 0.));
+        builder.line_to(Point::new(1., 0.));
+        builder.line_to(Point::new(1., 1.));
+        builder.line_to(Point::new(0., 1.));
+        builder.end(true);
+        builder.build()
+    }
+
+    fn builder() -> ShapeBuilder {
+        ShapeBuilder::new(0., [1.; 4])
+    }
+
+    fn command(indices: Range<u32>, op: ClipOp, depth: u32) -> ClipCommand {
+        ClipCommand { indices, op, depth }
+    }
+
+    fn push_clip(builder: &mut ShapeBuilder) {
+        builder.push_clip(&rect(), &FillOptions::default()).unwrap();
+    }
+
+    fn fill(builder: &mut ShapeBuilder) {
+        builder.fill(&rect(), &FillOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn unclipped_shapes_have_no_commands() {
+        let mut builder = builder();
+        fill(&mut builder);
+        assert_eq!(builder.geometry().1.len(), 6);
+        assert!(builder.clipping().is_empty());
+    }
+
+    #[test]
+    fn draws_before_a_clip_are_unclipped() {
+        let mut builder = builder();
+        fill(&mut builder);
+        push_clip(&mut builder);
+        fill(&mut builder);
+        builder.pop_clip();
+        assert_eq!(
+            builder.clipping(),
+            [
+                command(0..6, ClipOp::Draw, 0),
+                command(6..12, ClipOp::Push, 0),
+                command(12..18, ClipOp::Draw, 1),
+                command(6..12, ClipOp::Pop, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_clips_are_popped_in_reverse() {
+        let mut builder = builder();
+        push_clip(&mut builder);
+        push_clip(&mut builder);
+        fill(&mut builder);
+        builder.pop_clip();
+        fill(&mut builder);
+        builder.pop_clip();
+        fill(&mut builder);
+        assert_eq!(
+            builder.clipping(),
+            [
+                command(0..6, ClipOp::Push, 0),
+                command(6..12, ClipOp::Push, 1),
+                command(12..18, ClipOp::Draw, 2),
+                command(6..12, ClipOp::Pop, 2),
+                command(18..24, ClipOp::Draw, 1),
+                command(0..6, ClipOp::Pop, 1),
+                // The unclipped tail.
+                command(24..30, ClipOp::Draw, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn active_clips_are_popped_after_the_last_draw() {
+        let mut builder = builder();
+        push_clip(&mut builder);
+        push_clip(&mut builder);
+        fill(&mut builder);
+        assert_eq!(
+            builder.clipping(),
+            [
+                command(0..6, ClipOp::Push, 0),
+                command(6..12, ClipOp::Push, 1),
+                command(12..18, ClipOp::Draw, 2),
+                command(6..12, ClipOp::Pop, 2),
+                command(0..6, ClipOp::Pop, 1),
+            ]
+        );
+        // Preparing doesn't pop the clips, so later shapes are still clipped.
+        fill(&mut builder);
+        assert_eq!(builder.clipping()[2], command(12..24, ClipOp::Draw, 2));
+    }
+
+    #[test]
+    fn linear_colors_are_encoded_before_quantizing() {
+        let mut builder = ShapeBuilder::new(0., [0.002, 0.2, 1., 1.]);
+        fill(&mut builder);
+        let vertex = builder.geometry().0[0].encode(ColorSpace::Linear);
+        // Quantizing 0.002 first would round it up to 1/255, encoded as 13.
+        assert_eq!(vertex.color, Rgba8::new(7, 124, 255, 255));
+    }
+
+    #[test]
+    #[should_panic(expected = "without a matching push_clip")]
+    fn unmatched_pops_panic() {
+        builder().pop_clip();
+    }
+}
@@ -4,18 +4,18 @@ use lyon_tessellation::{
 };
 
 use crate::builder::ShapeBuilder;
-use crate::shape::Vertex;
+use crate::shape::TessellatedVertex;
 
-impl FillVertexConstructor<Vertex> for ShapeBuilder {
-    fn new_vertex(&mut self, mut vertex: FillVertex) -> Vertex {
+impl FillVertexConstructor<TessellatedVertex> for ShapeBuilder {
+    fn new_vertex(&mut self, mut vertex: FillVertex) -> TessellatedVertex {
         let position = vertex.position();
         let attributes = vertex.interpolated_attributes();
         self.new_vertex(position, attributes)
     }
 }
 
-impl StrokeVertexConstructor<Vertex> for ShapeBuilder {
-    fn new_vertex(&mut self, mut vertex: StrokeVertex) -> Vertex {
+impl StrokeVertexConstructor<TessellatedVertex> for ShapeBuilder {
+    fn new_vertex(&mut self, mut vertex: StrokeVertex) -> TessellatedVertex {
         let position = vertex.position();
         let attributes = vertex.interpolated_attributes();
         self.new_vertex(position, attributes)
@@ -58,6 +58,9 @@ impl GeometryBuilder for ShapeBuilder {
     fn abort_geometry(&mut self) {
         self.vertices.clear();
         self.indicies.clear();
+        self.clipping.clear();
+        self.clips.clear();
+        self.unclipped_start = 0;
     }
 }
 
@@ -0,0 +1,76 @@
+use easygpu::color::Rgba;
+use lyon_tessellation::math::Point;
+use lyon_tessellation::path::{BuilderWithAttributes, Path};
+
+/// Builds a `Path` with a color at every endpoint.
+///
+/// `ShapeBuilder` and `DashBuilder` interpolate the colors across the
+/// tessellated fill or along the stroke, which draws simple gradients without
+/// textures. Whether colors are interpolated in linear or sRGB space is
+/// chosen by the pipeline's `VertexShaderSource`: `Srgb` or
+/// `SrgbInterpolated`.
+pub struct ColorPathBuilder {
+    builder: BuilderWithAttributes,
+}
+
+impl Default for ColorPathBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColorPathBuilder {
+    pub fn new() -> Self {
+        Self {
+            builder: Path::builder_with_attributes(4),
+        }
+    }
+
+    /// Starts a sub-path at `at`.
+    pub fn begin(&mut self, at: Point, color: Rgba) {
+        self.builder.begin(at, &attributes(color));
+    }
+
+    pub fn line_to(&mut self, to: Point, color: Rgba) {
+        self.builder.line_to(to, &attributes(color));
+    }
+
+    /// Adds a curve to `to`. Colors are only specified at endpoints.
+    pub fn quadratic_bezier_to(&mut self, ctrl: Point, to: Point, color: Rgba) {
+        self.builder
+            .quadratic_bezier_to(ctrl, to, &attributes(color));
+    }
+
+    /// Adds a curve to `to`. Colors are only specified at endpoints.
+    pub fn cubic_bezier_to(&mut self, ctrl1: Point, ctrl2: Point, to: Point, color: Rgba) {
+        self.builder
+            .cubic_bezier_to(ctrl1, ctrl2, to, &attributes(color));
+    }
+
+    /// Ends the current sub-path, connecting it back to its start if `close`
+    /// is true.
+    pub fn end(&mut self, close: bool) {
+        self.builder.end(close);
+    }
+
+    /// Adds a closed polygon with a color at each point.
+    pub fn polygon<I: IntoIterator<Item = (Point, Rgba)>>(&mut self, points: I) {
+        let mut points = points.into_iter();
+        if let Some((first, color)) = points.next() {
+            self.begin(first, color);
+            for (point, color) in points {
+                self.line_to(point, color);
+            }
+            self.end(true);
+        }
+    }
+
+    pub fn build(self) -> Path {
+        self.builder.build()
+    }
+}
+
+/// The attributes `ShapeBuilder` reads a vertex color from.
+fn attributes(color: Rgba) -> [f32; 4] {
+    [color.r, color.g, color.b, color.a]
+}
@@ -0,0 +1,335 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use easygpu::culling::BoundingBox;
+use easygpu::prelude::*;
+use lyon_tessellation::path::Path;
+use lyon_tessellation::{
+    GeometryBuilder, GeometryBuilderError, StrokeGeometryBuilder, StrokeOptions, StrokeTessellator,
+    StrokeVertex, TessellationError, VertexId,
+};
+
+use crate::builder::ShapeBuilder;
+use crate::shape::{Shape, Vertex};
+use crate::Uniforms;
+
+/// A pipeline for rendering strokes built by a `DashBuilder` with a dash
+/// pattern.
+///
+/// The pattern is applied per fragment using the distance along the path, so
+/// moving it only updates a uniform. After `set_easy_pipeline`, bind a
+/// `DashMaterial`'s `binding` and draw the shapes.
+pub struct DashPipeline {
+    pipeline: PipelineCore,
+}
+
+/// The most lengths a `DashPattern` can have. Odd-length patterns are repeated
+/// to make them even, so they can have at most half as many.
+pub const MAX_DASH_LENGTHS: usize = 16;
+
+/// Alternating dash and gap lengths along a stroke, in path units.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DashPattern {
+    /// Dash and gap lengths, starting with a dash. Like SVG's
+    /// `stroke-dasharray`, an odd number of lengths is repeated to make it
+    /// even. An empty pattern draws a solid stroke.
+    pub lengths: Vec<f32>,
+    /// How far along the pattern the start of the path is. Increasing it over
+    /// time moves the dashes backwards along the path, like marching ants.
+    pub offset: f32,
+}
+
+impl DashPattern {
+    pub fn new(lengths: Vec<f32>, offset: f32) -> Self {
+        Self { lengths, offset }
+    }
+
+    /// Returns the pattern with a different `offset`.
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct DashUniforms {
+    lengths: [[f32; 4]; MAX_DASH_LENGTHS / 4],
+    count: u32,
+    offset: f32,
+    period: f32,
+    _padding: f32,
+}
+
+impl DashUniforms {
+    fn new(pattern: &DashPattern) -> Self {
+        let mut lengths = pattern.lengths.clone();
+        if lengths.len() % 2 == 1 {
+            lengths.extend_from_within(..);
+        }
+        assert!(
+            lengths.len() <= MAX_DASH_LENGTHS,
+            "dash patterns can have at most {} lengths, or {} if there is an odd number of them",
+            MAX_DASH_LENGTHS,
+            MAX_DASH_LENGTHS / 2
+        );
+        assert!(
+            lengths.iter().all(|length| *length >= 0.),
+            "dash lengths can't be negative"
+        );
+        let period = lengths.iter().sum::<f32>();
+        if period <= 0. {
+            lengths.clear();
+        }
+
+        let mut uniforms = Self::zeroed();
+        for (index, length) in lengths.iter().enumerate() {
+            uniforms.lengths[index / 4][index % 4] = *length;
+        }
+        uniforms.count = lengths.len() as u32;
+        uniforms.offset = pattern.offset;
+        uniforms.period = period;
+        uniforms
+    }
+}
+
+/// A `DashPattern` bound for drawing, created by `DashPipeline::material`.
+pub struct DashMaterial {
+    pub binding: BindingGroup,
+    uniforms: UniformBuffer,
+}
+
+impl DashMaterial {
+    /// Changes the pattern of draws using this material, such as to animate
+    /// its offset.
+    pub fn set_pattern(&self, renderer: &Renderer, pattern: &DashPattern) {
+        renderer.update_uniform_buffer(&[DashUniforms::new(pattern)], &self.uniforms);
+    }
+}
+
+impl DashPipeline {
+    /// Panics if the pattern has more than `MAX_DASH_LENGTHS` lengths, more
+    /// than half as many if there is an odd number of them, or if any of them
+    /// are negative.
+    pub fn material(&self, renderer: &Renderer, pattern: &DashPattern) -> DashMaterial {
+        let uniforms = renderer.create_uniform_buffer(&[DashUniforms::new(pattern)]);
+        let binding = BindingGroupBuilder::new(&self.pipeline.pipeline.layout.sets[1])
+            .uniforms(&uniforms)
+            .build(renderer);
+        DashMaterial { binding, uniforms }
+    }
+}
+
+impl<'a> AbstractPipeline<'a> for DashPipeline {
+    fn description() -> PipelineDescription<'a> {
+        PipelineDescription {
+            vertex_layout: &[VertexBufferDesc {
+                formats: &[
+                    VertexFormat::Floatx3,
+                    VertexFormat::UBytex4,
+                    VertexFormat::Float,
+                ],
+                step_mode: wgpu::VertexStepMode::Vertex,
+            }],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStages::VERTEX,
+                }]),
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStages::FRAGMENT,
+                }]),
+            ],
+            shader: include_str!("shaders/dash.wgsl"),
+        }
+    }
+
+    fn setup(pipeline: Pipeline, dev: &GpuContext) -> Self {
+        Self {
+            pipeline: Uniforms::setup(pipeline, dev),
+        }
+    }
+}
+
+impl<'a> PreparePipeline<'a> for DashPipeline {
+    type PrepareContext = ScreenTransformation<f32>;
+    type Uniforms = Uniforms;
+
+    fn prepare(
+        &'a self,
+        ortho: Self::PrepareContext,
+    ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)> {
+        Uniforms::prepare(&self.pipeline, ortho)
+    }
+}
+
+impl Deref for DashPipeline {
+    type Target = PipelineCore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pipeline
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct DashVertex {
+    position: [f32; 3],
+    color: Rgba8,
+    /// The distance along the path.
+    advancement: f32,
+}
+
+/// Builds strokes for a `DashPipeline`.
+#[derive(Default, Debug)]
+pub struct DashBuilder {
+    shape: ShapeBuilder,
+    /// The distance along the path of each vertex of `shape`.
+    advancements: Vec<f32>,
+
+    /// This RGBA color is used when tesselating a path with no color data
+    /// (Attributes in lyon terminology)
+    pub default_color: [f32; 4],
+}
+
+impl DashBuilder {
+    pub fn new(zdepth: f32, default_color: [f32; 4]) -> Self {
+        Self {
+            shape: ShapeBuilder::new(zdepth, default_color),
+            advancements: Vec::new(),
+            default_color,
+        }
+    }
+
+    /// Strokes a path from `lyon::path`. Every option of `StrokeOptions` is
+    /// supported, and the dash pattern follows the path across its caps and
+    /// joins.
+    pub fn stroke(
+        &mut self,
+        path: &Path,
+        options: &StrokeOptions,
+    ) -> Result<(), TessellationError> {
+        self.shape.default_color = self.default_color;
+        let mut tessellator = StrokeTessellator::new();
+        tessellator.tessellate_with_ids(
+            path.id_iter(),
+            path,
+            Some(path),
+            options,
+            &mut DashGeometry {
+                shape: &mut self.shape,
+                advancements: &mut self.advancements,
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Prepares and loads the strokes into the renderer. Vertex colors are
+    /// converted from the renderer's `ColorSpace`.
+    pub fn prepare(&self, renderer: &Renderer) -> Shape {
+        let color_space = renderer.color_space();
+        let (vertices, indices) = self.shape.geometry();
+        let vertices = vertices
+            .iter()
+            .zip(&self.advancements)
+            .map(|(vertex, advancement)| {
+                let Vertex { position, color } = vertex.encode(color_space);
+                DashVertex {
+                    position,
+                    color,
+                    advancement: *advancement,
+                }
+            })
+            .collect::<Vec<_>>();
+        Shape {
+            mesh: Arc::new(renderer.mesh(&vertices, indices)),
+            bounds: BoundingBox::from_points(vertices.iter().map(|v| v.position)),
+            clipping: Vec::new(),
+        }
+    }
+}
+
+/// Tessellates into a `ShapeBuilder`, recording the advancement of each
+/// vertex alongside it.
+struct DashGeometry<'a> {
+    shape: &'a mut ShapeBuilder,
+    advancements: &'a mut Vec<f32>,
+}
+
+impl GeometryBuilder for DashGeometry<'_> {
+    fn begin_geometry(&mut self) {
+        self.shape.begin_geometry();
+    }
+
+    fn end_geometry(&mut self) {
+        self.shape.end_geometry();
+    }
+
+    fn add_triangle(&mut self, a: VertexId, b: VertexId, c: VertexId) {
+        self.shape.add_triangle(a, b, c);
+    }
+
+    fn abort_geometry(&mut self) {
+        self.shape.abort_geometry();
+        self.advancements.clear();
+    }
+}
+
+impl StrokeGeometryBuilder for DashGeometry<'_> {
+    fn add_stroke_vertex(
+        &mut self,
+        vertex: StrokeVertex,
+    ) -> Result<VertexId, GeometryBuilderError> {
+        let advancement = vertex.advancement();
+        let id = self.shape.add_stroke_vertex(vertex)?;
+        self.advancements.push(advancement);
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uniforms(count: usize) -> DashUniforms {
+        DashUniforms::new(&DashPattern::new(vec![1.; count], 0.))
+    }
+
+    #[test]
+    fn odd_patterns_are_repeated() {
+        let uniforms = uniforms(3);
+        assert_eq!(uniforms.count, 6);
+        assert_eq!(uniforms.period, 6.);
+        assert_eq!(uniforms.lengths[1], [1., 1., 0., 0.]);
+    }
+
+    #[test]
+    fn patterns_fill_the_uniforms() {
+        assert_eq!(uniforms(MAX_DASH_LENGTHS).count, MAX_DASH_LENGTHS as u32);
+        assert_eq!(
+            uniforms(MAX_DASH_LENGTHS / 2 - 1).count,
+            MAX_DASH_LENGTHS as u32 - 2
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 16 lengths")]
+    fn long_patterns_panic() {
+        uniforms(MAX_DASH_LENGTHS + 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 16 lengths")]
+    fn long_odd_patterns_panic() {
+        uniforms(MAX_DASH_LENGTHS / 2 + 1);
+    }
+
+    #[test]
+    fn empty_periods_draw_solid_strokes() {
+        assert_eq!(uniforms(0).count, 0);
+        let zero = DashUniforms::new(&DashPattern::new(vec![0., 0.], 0.));
+        assert_eq!(zero.count, 0);
+    }
+}
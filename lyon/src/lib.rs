@@ -1,8 +1,22 @@
 mod builder;
+mod color_path;
+mod dash;
 mod pipeline;
+mod sdf;
 mod shape;
-pub use lyon_tessellation;
+#[cfg(feature = "usvg")]
+mod svg;
+mod tessellate;
+#[cfg(feature = "usvg")]
+pub use usvg;
+pub use {easygpu, lyon_tessellation};
 
 pub use self::builder::*;
+pub use self::color_path::*;
+pub use self::dash::*;
 pub use self::pipeline::*;
+pub use self::sdf::*;
 pub use self::shape::*;
+#[cfg(feature = "usvg")]
+pub use self::svg::*;
+pub use self::tessellate::*;
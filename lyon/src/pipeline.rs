@@ -5,6 +5,8 @@ use bytemuck::{Pod, Zeroable};
 use easygpu::prelude::*;
 use easygpu::wgpu::TextureFormat;
 
+use crate::shape::{ClipOp, Shape};
+
 /// A pipeline for rendering shapes.
 pub struct LyonPipeline<T> {
     pipeline: PipelineCore,
@@ -21,13 +23,48 @@ pub struct Uniforms {
     pub transform: [f32; 16],
 }
 
+impl Uniforms {
+    /// Creates the `PipelineCore` of a pipeline whose first set holds only
+    /// these uniforms, projecting with the identity until it is prepared.
+    pub(crate) fn setup(pipeline: Pipeline, dev: &GpuContext) -> PipelineCore {
+        let transform = ScreenTransformation::identity().to_array();
+        let ortho = ScreenTransformation::identity().to_array();
+        let uniforms = dev.create_uniform_buffer(&[Self { ortho, transform }]);
+        let bindings = BindingGroupBuilder::new(&pipeline.layout.sets[0])
+            .uniforms(&uniforms)
+            .build(dev);
+
+        PipelineCore {
+            pipeline,
+            uniforms,
+            bindings,
+        }
+    }
+
+    /// The uniforms of a pipeline created by `setup`, projecting with `ortho`.
+    pub(crate) fn prepare(
+        core: &PipelineCore,
+        ortho: ScreenTransformation<f32>,
+    ) -> Option<(&UniformBuffer, Vec<Self>)> {
+        let ortho = ortho.to_array();
+        let transform = ScreenTransformation::identity().to_array();
+        Some((&core.uniforms, vec![Self { transform, ortho }]))
+    }
+}
+
 pub trait VertexShaderSource {
     fn shader() -> &'static str;
 
     fn sampler_format() -> TextureFormat;
 }
 
+/// Renders to sRGB targets, linearizing vertex colors before they are
+/// interpolated across each triangle.
 pub struct Srgb;
+/// Renders to sRGB targets like `Srgb`, but interpolates vertex colors in sRGB
+/// space and linearizes them per fragment, like CSS gradients. Blends between
+/// saturated colors look darker in the middle than with `Srgb`.
+pub struct SrgbInterpolated;
 pub struct Normal;
 
 impl VertexShaderSource for Srgb {
@@ -40,50 +77,54 @@ impl VertexShaderSource for Srgb {
     }
 }
 
+impl VertexShaderSource for SrgbInterpolated {
+    fn shader() -> &'static str {
+        include_str!("shaders/shape_srgb_interpolated.wgsl")
+    }
+
+    fn sampler_format() -> TextureFormat {
+        TextureFormat::Bgra8UnormSrgb
+    }
+}
+
 impl<'a, T> AbstractPipeline<'a> for LyonPipeline<T>
 where
     T: VertexShaderSource,
 {
-    type PrepareContext = ScreenTransformation<f32>;
-    type Uniforms = Uniforms;
-
     fn description() -> PipelineDescription<'a> {
         PipelineDescription {
-            vertex_layout: &[VertexFormat::Floatx3, VertexFormat::UBytex4],
+            vertex_layout: &[VertexBufferDesc {
+                formats: &[VertexFormat::Floatx3, VertexFormat::UBytex4],
+                step_mode: wgpu::VertexStepMode::Vertex,
+            }],
             pipeline_layout: &[Set(&[Binding {
                 binding: BindingType::UniformBuffer,
                 stage: ShaderStages::VERTEX,
             }])],
-            shader: include_str!("shaders/shape.wgsl"),
+            shader: T::shader(),
         }
     }
 
-    fn setup(pipeline: Pipeline, dev: &Device) -> Self {
-        let transform = ScreenTransformation::identity().to_array();
-        let ortho = ScreenTransformation::identity().to_array();
-        let uniforms = dev.create_uniform_buffer(&[self::Uniforms { ortho, transform }]);
-        let bindings = dev.create_binding_group(&pipeline.layout.sets[0], &[&uniforms]);
-
+    fn setup(pipeline: Pipeline, dev: &GpuContext) -> Self {
         Self {
-            pipeline: PipelineCore {
-                pipeline,
-                uniforms,
-                bindings,
-            },
-            _phantom: PhantomData::default(),
+            pipeline: Uniforms::setup(pipeline, dev),
+            _phantom: PhantomData,
         }
     }
+}
+
+impl<'a, T> PreparePipeline<'a> for LyonPipeline<T>
+where
+    T: VertexShaderSource,
+{
+    type PrepareContext = ScreenTransformation<f32>;
+    type Uniforms = Uniforms;
 
     fn prepare(
         &'a self,
         ortho: Self::PrepareContext,
     ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)> {
-        let ortho = ortho.to_array();
-        let transform = ScreenTransformation::identity().to_array();
-        Some((
-            &self.pipeline.uniforms,
-            vec![self::Uniforms { transform, ortho }],
-        ))
+        Uniforms::prepare(&self.pipeline, ortho)
     }
 }
 
@@ -94,3 +135,103 @@ impl<T> Deref for LyonPipeline<T> {
         &self.pipeline
     }
 }
+
+/// A `LyonPipeline` that applies the clip paths of shapes built with
+/// `ShapeBuilder::push_clip`, using the stencil buffer.
+///
+/// The device needs a depth format with a stencil aspect, such as
+/// `Depth24PlusStencil8`. Passes clear the stencil, so every clipped shape
+/// drawn in a pass starts unclipped.
+pub struct ClipPipeline<T> {
+    pipeline: LyonPipeline<T>,
+    clipped: wgpu::RenderPipeline,
+    push: wgpu::RenderPipeline,
+    pop: wgpu::RenderPipeline,
+}
+
+impl<T> ClipPipeline<T>
+where
+    T: VertexShaderSource,
+{
+    /// Panics if the device's depth format has no stencil aspect.
+    pub fn new(device: &GpuContext, blending: Blending, format: TextureFormat) -> Self {
+        let pipeline: LyonPipeline<T> = device.pipeline(blending.clone(), format);
+        let shader = device.create_shader(LyonPipeline::<T>::description().shader);
+        let multisample = wgpu::MultisampleState {
+            count: device.sample_count(),
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        };
+        let create = |stencil| {
+            device.create_stencil_pipeline(
+                &pipeline.pipeline.pipeline.layout,
+                &pipeline.pipeline.pipeline.vertex_layouts,
+                blending.clone(),
+                &shader,
+                format,
+                multisample,
+                stencil,
+            )
+        };
+        let clipped = create(StencilMode::Equal);
+        let push = create(StencilMode::Increment);
+        let pop = create(StencilMode::Decrement);
+
+        Self {
+            pipeline,
+            clipped,
+            push,
+            pop,
+        }
+    }
+
+    /// Draws `shape` inside its clip paths, leaving the stencil as it was.
+    pub fn draw<'a>(&'a self, shape: &'a Shape, pass: &mut wgpu::RenderPass<'a>) {
+        pass.set_binding(&self.pipeline.bindings, &[]);
+        pass.set_easy_vertex_buffer(&shape.mesh.vertices);
+        pass.set_easy_index_buffer(&shape.mesh.indices);
+        if shape.clipping.is_empty() {
+            pass.set_pipeline(&self.clipped);
+            pass.set_stencil_reference(0);
+            RenderPassExt::draw_indexed(pass, shape.mesh.range.clone(), 0..1);
+            return;
+        }
+        for command in &shape.clipping {
+            pass.set_pipeline(match command.op {
+                ClipOp::Draw => &self.clipped,
+                ClipOp::Push => &self.push,
+                ClipOp::Pop => &self.pop,
+            });
+            pass.set_stencil_reference(command.depth);
+            RenderPassExt::draw_indexed(pass, command.indices.clone(), 0..1);
+        }
+    }
+
+    /// The pipeline used for the shapes' uniforms and unclipped drawing.
+    pub fn pipeline(&self) -> &LyonPipeline<T> {
+        &self.pipeline
+    }
+}
+
+impl<'a, T> PreparePipeline<'a> for ClipPipeline<T>
+where
+    T: VertexShaderSource,
+{
+    type PrepareContext = ScreenTransformation<f32>;
+    type Uniforms = Uniforms;
+
+    fn prepare(
+        &'a self,
+        ortho: Self::PrepareContext,
+    ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)> {
+        self.pipeline.prepare(ortho)
+    }
+}
+
+impl<T> Deref for ClipPipeline<T> {
+    type Target = PipelineCore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pipeline
+    }
+}
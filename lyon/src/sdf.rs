@@ -0,0 +1,458 @@
+use std::ops::Deref;
+
+use bytemuck::{Pod, Zeroable};
+use easygpu::figures::{Rect, Size};
+use easygpu::prelude::*;
+
+use crate::Uniforms;
+
+/// A pipeline for rendering glyphs and icons from a signed distance field
+/// atlas, which stays sharp at any scale.
+///
+/// The atlas and the look of each draw are bound with an `SdfMaterial`. After
+/// `set_easy_pipeline`, bind a material's `binding` and draw `SdfQuads`.
+pub struct SdfPipeline {
+    pipeline: PipelineCore,
+}
+
+/// The look of glyphs drawn by an `SdfPipeline`.
+///
+/// Widths are in the atlas's distance units: the edge of the shape is at
+/// 0.5, and texels `SdfBitmap::spread` away from it are at 0 and 1. An
+/// `outline_width` of 0.1 therefore outlines the shape with a fifth of the
+/// spread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SdfStyle {
+    pub color: Rgba,
+    /// Additional blur of every edge, in distance units.
+    pub softness: f32,
+    pub outline_color: Rgba,
+    /// How far the outline extends outside of the shape. 0 disables it.
+    pub outline_width: f32,
+    pub glow_color: Rgba,
+    /// How far the glow extends outside of the outline. 0 disables it.
+    pub glow_width: f32,
+}
+
+impl Default for SdfStyle {
+    fn default() -> Self {
+        Self {
+            color: Rgba::new(1., 1., 1., 1.),
+            softness: 0.,
+            outline_color: Rgba::TRANSPARENT,
+            outline_width: 0.,
+            glow_color: Rgba::TRANSPARENT,
+            glow_width: 0.,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct StyleUniforms {
+    color: [f32; 4],
+    outline_color: [f32; 4],
+    glow_color: [f32; 4],
+    softness: f32,
+    outline_width: f32,
+    glow_width: f32,
+    _padding: f32,
+}
+
+impl StyleUniforms {
+    /// Uniform colors are used by the shader as is, so colors given in sRGB
+    /// are linearized first.
+    fn new(style: &SdfStyle, color_space: ColorSpace) -> Self {
+        let linear = |color: Rgba| match color_space {
+            ColorSpace::Srgb => color.to_linear(),
+            ColorSpace::Linear => color,
+        };
+        let array = |color: Rgba| [color.r, color.g, color.b, color.a];
+        Self {
+            color: array(linear(style.color)),
+            outline_color: array(linear(style.outline_color)),
+            glow_color: array(linear(style.glow_color)),
+            softness: style.softness,
+            outline_width: style.outline_width,
+            glow_width: style.glow_width,
+            _padding: 0.,
+        }
+    }
+}
+
+/// An atlas bound with an `SdfStyle`, created by `SdfPipeline::material`.
+pub struct SdfMaterial {
+    pub binding: BindingGroup,
+    uniforms: UniformBuffer,
+}
+
+impl SdfMaterial {
+    /// Changes the style of draws using this material.
+    pub fn set_style(&self, renderer: &Renderer, style: &SdfStyle) {
+        renderer.update_uniform_buffer(
+            &[StyleUniforms::new(style, renderer.color_space())],
+            &self.uniforms,
+        );
+    }
+}
+
+impl SdfPipeline {
+    /// Binds `atlas`, an `SdfBitmap` texture, with `style`.
+    pub fn material<T: AsRef<wgpu::TextureView>>(
+        &self,
+        renderer: &Renderer,
+        atlas: &T,
+        sampler: &Sampler,
+        style: &SdfStyle,
+    ) -> SdfMaterial {
+        let uniforms =
+            renderer.create_uniform_buffer(&[StyleUniforms::new(style, renderer.color_space())]);
+        let binding = BindingGroupBuilder::new(&self.pipeline.pipeline.layout.sets[1])
+            .uniforms(&uniforms)
+            .texture(atlas)
+            .sampler(sampler)
+            .build(renderer);
+        SdfMaterial { binding, uniforms }
+    }
+}
+
+impl<'a> AbstractPipeline<'a> for SdfPipeline {
+    fn description() -> PipelineDescription<'a> {
+        PipelineDescription {
+            vertex_layout: &[VertexBufferDesc {
+                formats: &[VertexFormat::Floatx3, VertexFormat::Floatx2],
+                step_mode: wgpu::VertexStepMode::Vertex,
+            }],
+            pipeline_layout: &[
+                Set(&[Binding {
+                    binding: BindingType::UniformBuffer,
+                    stage: ShaderStages::VERTEX,
+                }]),
+                Set(&[
+                    Binding {
+                        binding: BindingType::UniformBuffer,
+                        stage: ShaderStages::FRAGMENT,
+                    },
+                    Binding {
+                        binding: BindingType::SampledTexture {
+                            multisampled: false,
+                        },
+                        stage: ShaderStages::FRAGMENT,
+                    },
+                    Binding {
+                        binding: BindingType::Sampler,
+                        stage: ShaderStages::FRAGMENT,
+                    },
+                ]),
+            ],
+            shader: include_str!("shaders/sdf.wgsl"),
+        }
+    }
+
+    fn setup(pipeline: Pipeline, dev: &GpuContext) -> Self {
+        Self {
+            pipeline: Uniforms::setup(pipeline, dev),
+        }
+    }
+}
+
+impl<'a> PreparePipeline<'a> for SdfPipeline {
+    type PrepareContext = ScreenTransformation<f32>;
+    type Uniforms = Uniforms;
+
+    fn prepare(
+        &'a self,
+        ortho: Self::PrepareContext,
+    ) -> Option<(&'a UniformBuffer, Vec<Self::Uniforms>)> {
+        Uniforms::prepare(&self.pipeline, ortho)
+    }
+}
+
+impl Deref for SdfPipeline {
+    type Target = PipelineCore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pipeline
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct SdfVertex {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+/// Textured quads for an `SdfPipeline`, one per glyph or icon.
+#[derive(Debug, Default, Clone)]
+pub struct SdfQuads {
+    pub vertices: Vec<SdfVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl SdfQuads {
+    /// Adds a quad covering `dst` in screen space, showing the `uv` region of
+    /// the atlas, such as one returned by `Animation::uv`.
+    pub fn push(&mut self, dst: Rect<f32>, uv: Rect<f32>, z: f32) {
+        let (x0, y0) = (dst.origin.x, dst.origin.y);
+        let (x1, y1) = (x0 + dst.size.width, y0 + dst.size.height);
+        let (u0, v0) = (uv.origin.x, uv.origin.y);
+        let (u1, v1) = (u0 + uv.size.width, v0 + uv.size.height);
+        let vertex = |x, y, u, v| SdfVertex {
+            position: [x, y, z],
+            uv: [u, v],
+        };
+
+        let first = self.vertices.len() as u32;
+        self.vertices.extend_from_slice(&[
+            vertex(x0, y0, u0, v0),
+            vertex(x1, y0, u1, v0),
+            vertex(x1, y1, u1, v1),
+            vertex(x0, y1, u0, v1),
+        ]);
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| first + index));
+    }
+
+    /// Uploads the quads, to be drawn with `RenderPassExt::draw_mesh`.
+    pub fn prepare(&self, renderer: &Renderer) -> Mesh {
+        renderer.mesh(&self.vertices, &self.indices)
+    }
+}
+
+/// A signed distance field generated from an alpha bitmap, ready to be used
+/// as an atlas by `SdfPipeline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdfBitmap {
+    pub size: Size<u32>,
+    /// The distance, in texels, that the field spans on each side of edges.
+    pub spread: u32,
+    /// One `R8Unorm` texel per pixel: 128 on edges, above inside the shape.
+    pub texels: Vec<u8>,
+}
+
+impl SdfBitmap {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    /// Computes the distance field of an alpha bitmap on the CPU, treating
+    /// pixels with an alpha of at least 128 as inside. Leave `spread` pixels
+    /// of margin around shapes so their fields don't overlap.
+    pub fn from_alpha(alpha: &[u8], size: Size<u32>, spread: u32) -> Self {
+        let (width, height) = (size.width as usize, size.height as usize);
+        assert_eq!(
+            alpha.len(),
+            width * height,
+            "alpha bitmap doesn't match its size"
+        );
+        assert!(spread > 0, "spread must be at least one texel");
+
+        let inside = alpha.iter().map(|&a| a >= 128).collect::<Vec<_>>();
+        let to_inside = squared_distances(&inside, width, height, true);
+        let to_outside = squared_distances(&inside, width, height, false);
+        let range = 2. * spread as f32;
+        let texels = inside
+            .iter()
+            .zip(to_inside.iter().zip(&to_outside))
+            .map(|(&inside, (to_inside, to_outside))| {
+                // Edges lie halfway between texel centers.
+                let distance = if inside {
+                    to_outside.sqrt() - 0.5
+                } else {
+                    0.5 - to_inside.sqrt()
+                };
+                ((0.5 + distance / range).clamp(0., 1.) * 255.).round() as u8
+            })
+            .collect();
+
+        Self {
+            size,
+            spread,
+            texels,
+        }
+    }
+
+    /// Uploads the field to a new texture.
+    pub fn to_texture(&self, renderer: &mut Renderer) -> Texture {
+        let texture = renderer.texture(
+            self.size,
+            Self::FORMAT,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            false,
+        );
+        renderer.submit(&[Op::Fill(&texture, &self.texels)]);
+        texture
+    }
+}
+
+/// A huge but finite distance, which keeps the transform's arithmetic NaN
+/// free.
+const FAR: f32 = 1e20;
+
+/// The squared distance from each texel to the nearest texel whose `inside`
+/// is `target`, using the exact two-pass transform of Felzenszwalb and
+/// Huttenlocher.
+fn squared_distances(inside: &[bool], width: usize, height: usize, target: bool) -> Vec<f32> {
+    let mut grid = inside
+        .iter()
+        .map(|&inside| if inside == target { 0. } else { FAR })
+        .collect::<Vec<f32>>();
+
+    let longest = width.max(height);
+    let mut line = vec![0.; longest];
+    let mut out = vec![0.; longest];
+    let mut parabolas = vec![0; longest];
+    let mut bounds = vec![0.; longest + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        transform_line(&line[..height], &mut out, &mut parabolas, &mut bounds);
+        for y in 0..height {
+            grid[y * width + x] = out[y];
+        }
+    }
+    for y in 0..height {
+        let row = &mut grid[y * width..][..width];
+        line[..width].copy_from_slice(row);
+        transform_line(&line[..width], &mut out, &mut parabolas, &mut bounds);
+        row.copy_from_slice(&out[..width]);
+    }
+
+    grid
+}
+
+/// The one-dimensional squared distance transform of `f`: the lower envelope
+/// of the parabolas rooted at each sample.
+fn transform_line(f: &[f32], out: &mut [f32], parabolas: &mut [usize], bounds: &mut [f32]) {
+    let intersection = |q: usize, p: usize| {
+        let (q, p) = (q as f32, p as f32);
+        ((f[q as usize] + q * q) - (f[p as usize] + p * p)) / (2. * (q - p))
+    };
+
+    let mut k = 0;
+    parabolas[0] = 0;
+    bounds[0] = f32::NEG_INFINITY;
+    bounds[1] = f32::INFINITY;
+    for q in 1..f.len() {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= bounds[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = f32::INFINITY;
+    }
+
+    k = 0;
+    for (q, out) in out.iter_mut().enumerate().take(f.len()) {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let offset = q as f32 - parabolas[k] as f32;
+        *out = offset * offset + f[parabolas[k]];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The texel that `from_alpha` stores for a signed `distance`.
+    fn texel(distance: f32, spread: u32) -> u8 {
+        ((0.5 + distance / (2. * spread as f32)).clamp(0., 1.) * 255.).round() as u8
+    }
+
+    fn transformed(f: &[f32]) -> Vec<f32> {
+        let mut out = vec![0.; f.len()];
+        let mut parabolas = vec![0; f.len()];
+        let mut bounds = vec![0.; f.len() + 1];
+        transform_line(f, &mut out, &mut parabolas, &mut bounds);
+        out
+    }
+
+    #[test]
+    fn transform_line_measures_squared_distances() {
+        assert_eq!(
+            transformed(&[0., FAR, FAR, FAR, FAR]),
+            [0., 1., 4., 9., 16.]
+        );
+        assert_eq!(
+            transformed(&[FAR, FAR, 0., FAR, FAR, FAR]),
+            [4., 1., 0., 1., 4., 9.]
+        );
+        assert_eq!(transformed(&[0., FAR, FAR, FAR, 0.]), [0., 1., 4., 1., 0.]);
+        // Existing distances from the other pass are kept when nearer.
+        assert_eq!(transformed(&[0., 1., FAR, FAR]), [0., 1., 2., 5.]);
+    }
+
+    #[test]
+    fn single_pixel_bitmaps() {
+        let field = SdfBitmap::from_alpha(&[255], Size::new(1, 1), 2);
+        assert_eq!(field.texels, [255]);
+        let field = SdfBitmap::from_alpha(&[0], Size::new(1, 1), 2);
+        assert_eq!(field.texels, [0]);
+
+        #[rustfmt::skip]
+        let alpha = [
+            0, 0, 0,
+            0, 255, 0,
+            0, 0, 0,
+        ];
+        let field = SdfBitmap::from_alpha(&alpha, Size::new(3, 3), 1);
+        let (corner, edge, center) = (texel(0.5 - 2f32.sqrt(), 1), texel(-0.5, 1), texel(0.5, 1));
+        assert_eq!(
+            field.texels,
+            [corner, edge, corner, edge, center, edge, corner, edge, corner]
+        );
+    }
+
+    #[test]
+    fn empty_bitmaps_are_outside_everywhere() {
+        let field = SdfBitmap::from_alpha(&[0; 12], Size::new(4, 3), 4);
+        assert_eq!(field.texels, [0; 12]);
+    }
+
+    #[test]
+    fn distances_along_a_row() {
+        let alpha = [255, 255, 255, 255, 0, 0, 0, 0];
+        let field = SdfBitmap::from_alpha(&alpha, Size::new(8, 1), 4);
+        let expected = (0..8).map(|x| texel(3.5 - x as f32, 4)).collect::<Vec<_>>();
+        assert_eq!(field.texels, expected);
+        // The edge falls between the two middle texels.
+        assert_eq!(field.texels[3], texel(0.5, 4));
+        assert_eq!(field.texels[4], texel(-0.5, 4));
+    }
+
+    #[test]
+    fn fields_are_symmetric() {
+        let size = 7;
+        let alpha = (0..size * size)
+            .map(|index| {
+                let (x, y) = (index % size, index / size);
+                if (2..5).contains(&x) && (1..6).contains(&y) {
+                    255
+                } else {
+                    0
+                }
+            })
+            .collect::<Vec<u8>>();
+        let field = SdfBitmap::from_alpha(&alpha, Size::new(size as u32, size as u32), 3);
+        let at = |x: usize, y: usize| field.texels[y * size + x];
+        for y in 0..size {
+            for x in 0..size {
+                assert_eq!(at(x, y), at(size - 1 - x, y), "at {x}, {y}");
+                assert_eq!(at(x, y), at(x, size - 1 - y), "at {x}, {y}");
+            }
+        }
+
+        // Swapping inside and outside negates every distance.
+        let inverted = alpha.iter().map(|a| 255 - a).collect::<Vec<_>>();
+        let inverse = SdfBitmap::from_alpha(&inverted, field.size, field.spread);
+        for (texel, inverse) in field.texels.iter().zip(&inverse.texels) {
+            assert!((255 - i16::from(*texel) - i16::from(*inverse)).abs() <= 1);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+struct Globals {
+    ortho: mat4x4<f32>,
+    transform: mat4x4<f32>,
+};
+
+struct Dash {
+    // Alternating dash and gap lengths, four per element.
+    lengths: array<vec4<f32>, 4>,
+    count: u32,
+    offset: f32,
+    period: f32,
+};
+
+@group(0) @binding(0) var<uniform> global: Globals;
+@group(1) @binding(0) var<uniform> dash: Dash;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) advancement: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) advancement: f32,
+};
+
+fn linearize(srgb: vec3<f32>) -> vec3<f32> {
+    let cutoff = srgb < vec3<f32>(0.04045);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    let lower = srgb / vec3<f32>(12.92);
+
+    return select(higher, lower, cutoff);
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.color = vec4<f32>(linearize(input.color.rgb), input.color.a);
+    output.advancement = input.advancement;
+    output.position = global.ortho * global.transform * vec4<f32>(input.position, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    if (dash.count == 0u) {
+        return input.color;
+    }
+
+    let along = input.advancement + dash.offset;
+    let position = along - floor(along / dash.period) * dash.period;
+    // Antialias dash ends over about a pixel.
+    let aa = max(fwidth(input.advancement), 0.0001);
+
+    // Dashes of the neighboring periods are included so ends that cross the
+    // start of a period are antialiased too.
+    var coverage = 0.0;
+    for (var shift = -1; shift <= 1; shift = shift + 1) {
+        var start = f32(shift) * dash.period;
+        for (var i = 0u; i < dash.count; i = i + 1u) {
+            let end = start + dash.lengths[i / 4u][i % 4u];
+            if (i % 2u == 0u) {
+                let inside = min(position - start, end - position);
+                coverage = max(coverage, clamp(inside / aa + 0.5, 0.0, 1.0));
+            }
+            start = end;
+        }
+    }
+    if (coverage <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(input.color.rgb, input.color.a * coverage);
+}
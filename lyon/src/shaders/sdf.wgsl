@@ -0,0 +1,61 @@
+struct Globals {
+    ortho: mat4x4<f32>,
+    transform: mat4x4<f32>,
+};
+
+struct Style {
+    color: vec4<f32>,
+    outline_color: vec4<f32>,
+    glow_color: vec4<f32>,
+    softness: f32,
+    outline_width: f32,
+    glow_width: f32,
+};
+
+@group(0) @binding(0) var<uniform> global: Globals;
+@group(1) @binding(0) var<uniform> style: Style;
+@group(1) @binding(1) var atlas: texture_2d<f32>;
+@group(1) @binding(2) var atlas_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.uv = input.uv;
+    output.position = global.ortho * global.transform * vec4<f32>(input.position, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Positive inside the shape, in the atlas's distance units.
+    let distance = textureSample(atlas, atlas_sampler, input.uv).r - 0.5;
+    // Antialias over about a pixel, widened by the style's softness.
+    let aa = max(fwidth(distance) * 0.75, 0.0001) + style.softness;
+
+    let fill = smoothstep(-aa, aa, distance);
+    let outlined = smoothstep(-aa, aa, distance + style.outline_width);
+    var body = mix(style.outline_color, style.color, fill);
+    body.a = body.a * outlined;
+
+    let reach = style.outline_width + style.glow_width;
+    let glow = select(
+        0.0,
+        smoothstep(-reach, -style.outline_width, distance),
+        style.glow_width > 0.0,
+    );
+    let glow_alpha = style.glow_color.a * glow * (1.0 - body.a);
+
+    let alpha = body.a + glow_alpha;
+    let rgb = (body.rgb * body.a + style.glow_color.rgb * glow_alpha) / max(alpha, 0.0001);
+    return vec4<f32>(rgb, alpha);
+}
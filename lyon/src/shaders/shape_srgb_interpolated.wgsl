@@ -0,0 +1,38 @@
+struct Globals {
+    ortho: mat4x4<f32>,
+    transform: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> global: Globals;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Still sRGB-encoded, so it is interpolated in sRGB space.
+    @location(0) color: vec4<f32>,
+};
+
+fn linearize(srgb: vec3<f32>) -> vec3<f32> {
+    let cutoff = srgb < vec3<f32>(0.04045);
+    let higher = pow((srgb + vec3<f32>(0.055)) / vec3<f32>(1.055), vec3<f32>(2.4));
+    let lower = srgb / vec3<f32>(12.92);
+
+    return select(higher, lower, cutoff);
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.color = input.color;
+    output.position = global.ortho * global.transform * vec4<f32>(input.position, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(linearize(input.color.rgb), input.color.a);
+}
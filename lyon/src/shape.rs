@@ -1,8 +1,11 @@
+use std::ops::Range;
 use std::sync::Arc;
 
 use bytemuck::{Pod, Zeroable};
-use easygpu::buffers::{IndexBuffer, VertexBuffer};
-use easygpu::color::Rgba8;
+use easygpu::color::{ColorSpace, Rgba, Rgba8};
+use easygpu::culling::BoundingBox;
+use easygpu::mesh::Mesh;
+use easygpu::renderer::RenderPassExt;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -11,14 +14,58 @@ pub(crate) struct Vertex {
     pub color: Rgba8,
 }
 
+/// A vertex from tessellation, whose color is kept unquantized until the
+/// shape is uploaded with a known `ColorSpace`.
+#[derive(Copy, Clone, Debug, Default)]
+pub(crate) struct TessellatedVertex {
+    pub position: [f32; 3],
+    pub color: Rgba,
+}
+
+impl TessellatedVertex {
+    /// Converts the color from `color_space` to the 8-bit sRGB encoding of
+    /// vertex buffers.
+    pub fn encode(self, color_space: ColorSpace) -> Vertex {
+        Vertex {
+            position: self.position,
+            color: color_space.to_encoded(self.color).into(),
+        }
+    }
+}
+
+/// What a `ClipCommand` does with its triangles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClipOp {
+    /// Draws the triangles inside the active clips.
+    Draw,
+    /// Adds the triangles to the clip region, intersecting them with the
+    /// active clips.
+    Push,
+    /// Removes the triangles of the matching `Push` from the clip region.
+    Pop,
+}
+
+/// One step of drawing a clipped `Shape`, recorded by
+/// `ShapeBuilder::push_clip` and `ShapeBuilder::pop_clip`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClipCommand {
+    /// The range of elements in the mesh's indices.
+    pub indices: Range<u32>,
+    pub op: ClipOp,
+    /// The number of clips active before a `Draw` or `Push`, or before a
+    /// `Pop` removes its clip. This is the stencil reference of the step.
+    pub depth: u32,
+}
+
 /// Shape is a loaded, prepared ShapeBuilder that is ready to be drawn
 pub struct Shape {
-    /// Number of indices contained in `indices`
-    pub index_count: u32,
-    /// The vertices stored in a vertex buffer
-    pub vertices: Arc<VertexBuffer>,
-    /// An index buffer representing a TriangleList of indices within `vertices`
-    pub indices: Arc<IndexBuffer>,
+    /// The vertices and TriangleList indices of the tesselated shape
+    pub mesh: Arc<Mesh>,
+    /// The bounds of every vertex in `mesh`, or `None` if the shape is empty
+    pub bounds: Option<BoundingBox>,
+    /// The steps of drawing the shape with a `ClipPipeline`, or empty if it
+    /// has no clip paths.
+    pub clipping: Vec<ClipCommand>,
 }
 
 impl Shape {
@@ -29,9 +76,20 @@ impl Shape {
     /// # Arguments
     ///
     /// * `pass`- The render pass to draw to.
+    ///
+    /// Clip paths are skipped and the rest of the shape is drawn unclipped.
+    /// Use `ClipPipeline::draw` to apply them.
     pub fn draw<'a>(&'a self, pass: &mut easygpu::wgpu::RenderPass<'a>) {
-        pass.set_vertex_buffer(0, self.vertices.slice());
-        pass.set_index_buffer(self.indices.slice(), easygpu::wgpu::IndexFormat::Uint16);
-        pass.draw_indexed(0..self.index_count, 0, 0..1)
+        if self.clipping.is_empty() {
+            pass.draw_mesh(&self.mesh);
+            return;
+        }
+        pass.set_easy_vertex_buffer(&self.mesh.vertices);
+        pass.set_easy_index_buffer(&self.mesh.indices);
+        for command in &self.clipping {
+            if command.op == ClipOp::Draw {
+                RenderPassExt::draw_indexed(pass, command.indices.clone(), 0..1);
+            }
+        }
     }
 }
@@ -0,0 +1,333 @@
+use std::fmt;
+
+use easygpu::figures::Size;
+use easygpu::renderer::Renderer;
+use lyon_tessellation::math::point;
+use lyon_tessellation::path::Path;
+use lyon_tessellation::{
+    FillOptions, FillRule, LineCap, LineJoin, StrokeOptions, TessellationError,
+};
+use usvg::tiny_skia_path::PathSegment;
+use usvg::{Group, Node, Paint, PaintOrder, Transform, Tree};
+
+use crate::tessellate::{tessellate, PathMode, PreparedShapes, ShapeData, ShapePath};
+
+/// An error from `load_svg`.
+#[derive(Debug)]
+pub enum SvgError {
+    Parse(usvg::Error),
+    Tessellation(TessellationError),
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "error parsing svg: {}", err),
+            Self::Tessellation(err) => write!(f, "error tessellating svg: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for SvgError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            Self::Tessellation(_) => None,
+        }
+    }
+}
+
+impl From<usvg::Error> for SvgError {
+    fn from(err: usvg::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<TessellationError> for SvgError {
+    fn from(err: TessellationError) -> Self {
+        Self::Tessellation(err)
+    }
+}
+
+/// Parses an SVG document, tessellates it to fit `size` pixels, and uploads
+/// it. See `tessellate_svg` for what is supported.
+pub fn load_svg(
+    renderer: &Renderer,
+    data: &[u8],
+    size: Size<f32>,
+) -> Result<PreparedShapes, SvgError> {
+    let tree = Tree::from_data(data, &usvg::Options::default())?;
+    Ok(tessellate_svg(&tree, size)?.upload(renderer))
+}
+
+/// Tessellates the fills and strokes of a parsed SVG on the CPU.
+///
+/// The document is scaled to fit `size` pixels, keeping its aspect ratio and
+/// centering it like an `<img>` of that size would. Gradients are drawn with
+/// the color of their first stop, and group opacity is applied to each path
+/// separately. Patterns, images, text, clip paths, masks and filters are
+/// ignored.
+pub fn tessellate_svg(tree: &Tree, size: Size<f32>) -> Result<ShapeData, TessellationError> {
+    let document = tree.size();
+    let scale = (size.width / document.width()).min(size.height / document.height());
+    let fit = Transform::from_row(
+        scale,
+        0.,
+        0.,
+        scale,
+        (size.width - document.width() * scale) / 2.,
+        (size.height - document.height() * scale) / 2.,
+    );
+
+    let mut paths = Vec::new();
+    collect_paths(tree.root(), fit, 1., &mut paths);
+    tessellate(paths.iter().map(|(path, mode, color)| ShapePath {
+        path,
+        mode: *mode,
+        color: *color,
+        zdepth: 0.,
+    }))
+}
+
+fn collect_paths(
+    group: &Group,
+    fit: Transform,
+    opacity: f32,
+    paths: &mut Vec<(Path, PathMode, [f32; 4])>,
+) {
+    let opacity = opacity * group.opacity().get();
+    for node in group.children() {
+        let svg_path = match node {
+            Node::Group(group) => {
+                collect_paths(group, fit, opacity, paths);
+                continue;
+            }
+            Node::Path(path) if path.is_visible() => path,
+            _ => continue,
+        };
+
+        let transform = svg_path.abs_transform().post_concat(fit);
+        let path = convert_path(svg_path.data(), transform);
+
+        let fill = svg_path.fill().and_then(|fill| {
+            let color = paint_color(fill.paint(), opacity * fill.opacity().get())?;
+            let rule = match fill.rule() {
+                usvg::FillRule::NonZero => FillRule::NonZero,
+                usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+            };
+            Some((
+                PathMode::Fill(FillOptions::default().with_fill_rule(rule)),
+                color,
+            ))
+        });
+        let stroke = svg_path.stroke().and_then(|stroke| {
+            let color = paint_color(stroke.paint(), opacity * stroke.opacity().get())?;
+            // Strokes are tessellated after transforming, so scale their width
+            // by the transform's average scale.
+            let scale = (transform.sx * transform.sy - transform.kx * transform.ky)
+                .abs()
+                .sqrt();
+            let options = StrokeOptions::default()
+                .with_line_width(stroke.width().get() * scale)
+                .with_line_cap(match stroke.linecap() {
+                    usvg::LineCap::Butt => LineCap::Butt,
+                    usvg::LineCap::Round => LineCap::Round,
+                    usvg::LineCap::Square => LineCap::Square,
+                })
+                .with_line_join(match stroke.linejoin() {
+                    usvg::LineJoin::Miter => LineJoin::Miter,
+                    usvg::LineJoin::MiterClip => LineJoin::MiterClip,
+                    usvg::LineJoin::Round => LineJoin::Round,
+                    usvg::LineJoin::Bevel => LineJoin::Bevel,
+                })
+                .with_miter_limit(stroke.miterlimit().get().max(1.));
+            Some((PathMode::Stroke(options), color))
+        });
+
+        let order = match svg_path.paint_order() {
+            PaintOrder::FillAndStroke => [fill, stroke],
+            PaintOrder::StrokeAndFill => [stroke, fill],
+        };
+        for (mode, color) in order.iter().flatten() {
+            paths.push((path.clone(), *mode, *color));
+        }
+    }
+}
+
+fn convert_path(data: &usvg::tiny_skia_path::Path, transform: Transform) -> Path {
+    let map = |mut p: usvg::tiny_skia_path::Point| {
+        transform.map_point(&mut p);
+        point(p.x, p.y)
+    };
+
+    let mut builder = Path::builder();
+    let mut open = false;
+    for segment in data.segments() {
+        match segment {
+            PathSegment::MoveTo(to) => {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(map(to));
+                open = true;
+            }
+            PathSegment::LineTo(to) => {
+                builder.line_to(map(to));
+            }
+            PathSegment::QuadTo(ctrl, to) => {
+                builder.quadratic_bezier_to(map(ctrl), map(to));
+            }
+            PathSegment::CubicTo(ctrl1, ctrl2, to) => {
+                builder.cubic_bezier_to(map(ctrl1), map(ctrl2), map(to));
+            }
+            PathSegment::Close => {
+                if open {
+                    builder.end(true);
+                    open = false;
+                }
+            }
+        }
+    }
+    if open {
+        builder.end(false);
+    }
+    builder.build()
+}
+
+/// The sRGB color of a paint, or `None` for patterns and invisible paints.
+fn paint_color(paint: &Paint, opacity: f32) -> Option<[f32; 4]> {
+    let (color, opacity) = match paint {
+        Paint::Color(color) => (*color, opacity),
+        Paint::LinearGradient(gradient) => {
+            let stop = gradient.stops().first()?;
+            (stop.color(), opacity * stop.opacity().get())
+        }
+        Paint::RadialGradient(gradient) => {
+            let stop = gradient.stops().first()?;
+            (stop.color(), opacity * stop.opacity().get())
+        }
+        Paint::Pattern(_) => return None,
+    };
+    if opacity <= 0. {
+        return None;
+    }
+    Some([
+        f32::from(color.red) / 255.,
+        f32::from(color.green) / 255.,
+        f32::from(color.blue) / 255.,
+        opacity,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use easygpu::color::Rgba8;
+
+    use super::*;
+    use crate::shape::TessellatedVertex;
+
+    fn tessellated(svg: &str, size: Size<f32>) -> Vec<TessellatedVertex> {
+        let tree = Tree::from_data(svg.as_bytes(), &usvg::Options::default()).unwrap();
+        tessellate_svg(&tree, size)
+            .unwrap()
+            .vertices()
+            .copied()
+            .collect()
+    }
+
+    fn color(vertex: &TessellatedVertex) -> Rgba8 {
+        vertex.color.into()
+    }
+
+    fn bounds(vertices: &[TessellatedVertex]) -> ([f32; 2], [f32; 2]) {
+        vertices
+            .iter()
+            .fold(([f32::MAX; 2], [f32::MIN; 2]), |(min, max), vertex| {
+                let [x, y, _] = vertex.position;
+                (
+                    [min[0].min(x), min[1].min(y)],
+                    [max[0].max(x), max[1].max(y)],
+                )
+            })
+    }
+
+    #[test]
+    fn documents_are_fit_and_centered() {
+        let vertices = tessellated(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10" viewBox="0 0 2 1">
+                <rect width="2" height="1" fill="red"/>
+            </svg>"#,
+            Size::new(100., 100.),
+        );
+        assert_eq!(bounds(&vertices), ([0., 25.], [100., 75.]));
+    }
+
+    #[test]
+    fn fills_and_strokes_follow_the_paint_order() {
+        let svg = |order: &str| {
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                    <rect x="2" y="2" width="6" height="6" fill="red" stroke="blue" paint-order="{}"/>
+                </svg>"#,
+                order
+            )
+        };
+        let red = Rgba8::new(255, 0, 0, 255);
+        let blue = Rgba8::new(0, 0, 255, 255);
+
+        let normal = tessellated(&svg("normal"), Size::new(10., 10.));
+        assert_eq!(color(normal.first().unwrap()), red);
+        assert_eq!(color(normal.last().unwrap()), blue);
+
+        let stroke_first = tessellated(&svg("stroke"), Size::new(10., 10.));
+        assert_eq!(color(stroke_first.first().unwrap()), blue);
+        assert_eq!(color(stroke_first.last().unwrap()), red);
+    }
+
+    #[test]
+    fn stroke_widths_are_scaled() {
+        let vertices = tessellated(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+                <path d="M 0 5 L 20 5" fill="none" stroke="black" stroke-width="2"/>
+            </svg>"#,
+            Size::new(100., 100.),
+        );
+        // Scaled by 5 and moved down by 25 to center the document.
+        assert_eq!(bounds(&vertices), ([0., 45.], [100., 55.]));
+    }
+
+    #[test]
+    fn gradients_use_their_first_stop() {
+        let vertices = tessellated(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                <linearGradient id="gradient">
+                    <stop offset="0" stop-color="lime" stop-opacity="0.8"/>
+                    <stop offset="1" stop-color="blue"/>
+                </linearGradient>
+                <rect width="10" height="10" fill="url(#gradient)"/>
+            </svg>"#,
+            Size::new(10., 10.),
+        );
+        assert!(vertices
+            .iter()
+            .all(|vertex| color(vertex) == Rgba8::new(0, 255, 0, 204)));
+    }
+
+    #[test]
+    fn opacity_is_folded_into_each_path() {
+        let vertices = tessellated(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                <g opacity="0.5">
+                    <g opacity="0.5">
+                        <rect width="5" height="10" fill="white" fill-opacity="0.8"/>
+                    </g>
+                    <rect x="5" width="5" height="10" fill="white"/>
+                </g>
+            </svg>"#,
+            Size::new(10., 10.),
+        );
+        let mut alphas: Vec<u8> = vertices.iter().map(|vertex| color(vertex).a).collect();
+        alphas.dedup();
+        assert_eq!(alphas, [51, 128]);
+    }
+}
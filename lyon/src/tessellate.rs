@@ -0,0 +1,314 @@
+use std::iter::FromIterator;
+
+use easygpu::culling::BoundingBox;
+use easygpu::renderer::Renderer;
+use lyon_tessellation::path::Path;
+use lyon_tessellation::{FillOptions, StrokeOptions, TessellationError};
+
+use crate::builder::{upload, ShapeBuilder};
+use crate::pipeline::{ClipPipeline, VertexShaderSource};
+use crate::shape::{ClipCommand, ClipOp, Shape, TessellatedVertex};
+
+/// How `tessellate` turns a path into triangles.
+#[derive(Debug, Clone, Copy)]
+pub enum PathMode {
+    Fill(FillOptions),
+    Stroke(StrokeOptions),
+}
+
+/// A path for `tessellate`.
+#[derive(Debug, Clone, Copy)]
+pub struct ShapePath<'a> {
+    pub path: &'a Path,
+    pub mode: PathMode,
+    /// The RGBA color of paths without color attributes.
+    pub color: [f32; 4],
+    pub zdepth: f32,
+}
+
+impl<'a> ShapePath<'a> {
+    pub fn fill(path: &'a Path, color: [f32; 4]) -> Self {
+        Self {
+            path,
+            mode: PathMode::Fill(FillOptions::default()),
+            color,
+            zdepth: 0.,
+        }
+    }
+
+    pub fn stroke(path: &'a Path, options: StrokeOptions, color: [f32; 4]) -> Self {
+        Self {
+            path,
+            mode: PathMode::Stroke(options),
+            color,
+            zdepth: 0.,
+        }
+    }
+}
+
+/// Tessellates `paths` on the CPU, without touching the GPU.
+///
+/// The result can be sent back to the render thread and uploaded with
+/// `ShapeData::upload`, so large scenes can be tessellated on other threads
+/// while rendering continues. Results tessellated in parallel can be combined
+/// with `ShapeData::append`.
+pub fn tessellate<'a, I>(paths: I) -> Result<ShapeData, TessellationError>
+where
+    I: IntoIterator<Item = ShapePath<'a>>,
+{
+    let mut data = ShapeData::default();
+    for path in paths {
+        let mut builder = ShapeBuilder::new(path.zdepth, path.color);
+        match path.mode {
+            PathMode::Fill(options) => builder.fill(path.path, &options)?,
+            PathMode::Stroke(options) => builder.stroke(path.path, &options)?,
+        }
+        data.push(builder);
+    }
+    Ok(data)
+}
+
+/// A batch of vertices small enough for 16-bit indices.
+#[derive(Debug, Clone, Default)]
+struct Chunk {
+    vertices: Vec<TessellatedVertex>,
+    indices: Vec<u16>,
+    clipping: Vec<ClipCommand>,
+}
+
+impl Chunk {
+    /// The clip commands of the chunk, with unclipped chunks drawn as a
+    /// whole.
+    fn clip_commands(&self) -> Vec<ClipCommand> {
+        if self.clipping.is_empty() {
+            vec![ClipCommand {
+                indices: 0..self.indices.len() as u32,
+                op: ClipOp::Draw,
+                depth: 0,
+            }]
+        } else {
+            self.clipping.clone()
+        }
+    }
+}
+
+/// Tessellated shapes that haven't been uploaded yet, returned by
+/// `tessellate`.
+///
+/// Shapes are packed into as few meshes as their 16-bit indices allow.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeData {
+    chunks: Vec<Chunk>,
+}
+
+impl ShapeData {
+    /// Adds the shapes of a `ShapeBuilder`.
+    pub fn push(&mut self, builder: ShapeBuilder) {
+        let (vertices, indices, clipping) = builder.into_parts();
+        self.push_chunk(Chunk {
+            vertices,
+            indices,
+            clipping,
+        });
+    }
+
+    /// Moves the shapes of `other` into this data, after the existing ones.
+    pub fn append(&mut self, other: ShapeData) {
+        for chunk in other.chunks {
+            self.push_chunk(chunk);
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.chunks.iter().map(|chunk| chunk.vertices.len()).sum()
+    }
+
+    /// Every vertex, in the order it was tessellated.
+    #[cfg(all(test, feature = "usvg"))]
+    pub(crate) fn vertices(&self) -> impl Iterator<Item = &TessellatedVertex> {
+        self.chunks.iter().flat_map(|chunk| &chunk.vertices)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.iter().all(|chunk| chunk.indices.is_empty())
+    }
+
+    /// Uploads the shapes, one mesh per chunk.
+    pub fn upload(&self, renderer: &Renderer) -> PreparedShapes {
+        PreparedShapes {
+            shapes: self
+                .chunks
+                .iter()
+                .map(|chunk| {
+                    upload(
+                        renderer,
+                        &chunk.vertices,
+                        &chunk.indices,
+                        chunk.clipping.clone(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn push_chunk(&mut self, chunk: Chunk) {
+        if chunk.indices.is_empty() {
+            return;
+        }
+        match self.chunks.last_mut() {
+            Some(last) if last.vertices.len() + chunk.vertices.len() <= u16::MAX as usize => {
+                if !last.clipping.is_empty() || !chunk.clipping.is_empty() {
+                    let start = last.indices.len() as u32;
+                    let mut clipping = last.clip_commands();
+                    clipping.extend(
+                        chunk
+                            .clip_commands()
+                            .into_iter()
+                            .map(|command| ClipCommand {
+                                indices: command.indices.start + start..command.indices.end + start,
+                                ..command
+                            }),
+                    );
+                    last.clipping = clipping;
+                }
+                let offset = last.vertices.len() as u16;
+                last.vertices.extend(chunk.vertices);
+                last.indices
+                    .extend(chunk.indices.into_iter().map(|index| index + offset));
+            }
+            _ => self.chunks.push(chunk),
+        }
+    }
+}
+
+impl FromIterator<ShapeData> for ShapeData {
+    fn from_iter<I: IntoIterator<Item = ShapeData>>(iter: I) -> Self {
+        let mut data = ShapeData::default();
+        for other in iter {
+            data.append(other);
+        }
+        data
+    }
+}
+
+/// Shapes uploaded by `ShapeData::upload`.
+pub struct PreparedShapes {
+    pub shapes: Vec<Shape>,
+}
+
+impl PreparedShapes {
+    /// Draws every shape to the pass. Set the pipeline first.
+    pub fn draw<'a>(&'a self, pass: &mut easygpu::wgpu::RenderPass<'a>) {
+        for shape in &self.shapes {
+            shape.draw(pass);
+        }
+    }
+
+    /// Draws every shape to the pass inside its clip paths.
+    pub fn draw_clipped<'a, T: VertexShaderSource>(
+        &'a self,
+        pipeline: &'a ClipPipeline<T>,
+        pass: &mut easygpu::wgpu::RenderPass<'a>,
+    ) {
+        for shape in &self.shapes {
+            pipeline.draw(shape, pass);
+        }
+    }
+
+    /// The bounds of every shape, or `None` if there are none.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(
+            self.shapes
+                .iter()
+                .filter_map(|shape| shape.bounds)
+                .flat_map(|bounds| [bounds.min, bounds.max]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chunk of `vertices` vertices drawn as triangles over all of them.
+    fn chunk(vertices: usize) -> Chunk {
+        Chunk {
+            vertices: vec![TessellatedVertex::default(); vertices],
+            indices: (0..vertices as u16).collect(),
+            clipping: Vec::new(),
+        }
+    }
+
+    fn pushed(chunks: impl IntoIterator<Item = Chunk>) -> ShapeData {
+        let mut data = ShapeData::default();
+        for chunk in chunks {
+            data.push_chunk(chunk);
+        }
+        data
+    }
+
+    #[test]
+    fn pushed_chunks_are_merged_with_offset_indices() {
+        let data = pushed([chunk(3), chunk(6)]);
+        assert_eq!(data.chunks.len(), 1);
+        assert_eq!(data.vertex_count(), 9);
+        assert_eq!(data.chunks[0].indices[..3], [0, 1, 2]);
+        assert_eq!(data.chunks[0].indices[3..], [3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn empty_chunks_are_skipped() {
+        assert!(pushed([Chunk::default()]).chunks.is_empty());
+        assert_eq!(
+            pushed([chunk(3), Chunk::default(), chunk(3)]).chunks.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn chunks_are_split_at_the_u16_limit() {
+        let first = u16::MAX as usize - 3;
+
+        // Exactly filling the chunk keeps the last index in range.
+        let data = pushed([chunk(first), chunk(3), chunk(1)]);
+        assert_eq!(data.chunks.len(), 2);
+        assert_eq!(data.chunks[0].vertices.len(), u16::MAX as usize);
+        assert_eq!(
+            data.chunks[0].indices[first..],
+            [u16::MAX - 3, u16::MAX - 2, u16::MAX - 1]
+        );
+        // The chunk that no longer fits starts over at zero.
+        assert_eq!(data.chunks[1].indices, [0]);
+
+        let split = pushed([chunk(first), chunk(4)]);
+        assert_eq!(split.chunks.len(), 2);
+        assert_eq!(split.chunks[1].indices, [0, 1, 2, 3]);
+        assert_eq!(split.vertex_count(), first + 4);
+    }
+
+    #[test]
+    fn merged_clip_commands_are_offset() {
+        let mut clipped = chunk(3);
+        clipped.clipping = vec![ClipCommand {
+            indices: 0..3,
+            op: ClipOp::Push,
+            depth: 0,
+        }];
+        let data = pushed([chunk(6), clipped]);
+        assert_eq!(
+            data.chunks[0].clipping,
+            [
+                ClipCommand {
+                    indices: 0..6,
+                    op: ClipOp::Draw,
+                    depth: 0,
+                },
+                ClipCommand {
+                    indices: 6..9,
+                    op: ClipOp::Push,
+                    depth: 0,
+                },
+            ]
+        );
+    }
+}